@@ -0,0 +1,129 @@
+use crate::env::PaddleAction;
+use crate::input::{Action, Input};
+use crate::state::State;
+use crate::util;
+use crate::world::COURT_HALF_WIDTH;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.name() == name)
+    }
+
+    // the paddle only moves on one tick in this many, slower paddles lose steep shots
+    fn move_every(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Normal | Difficulty::Hard => 1,
+        }
+    }
+
+    // how close the incoming ball has to be, across the court, before the paddle reacts
+    fn reaction_distance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 0.7,
+            Difficulty::Hard => 2.0 * COURT_HALF_WIDTH,
+        }
+    }
+
+    // whether the paddle heads for where the ball will arrive rather than where it is now
+    fn predicts(&self) -> bool {
+        *self == Difficulty::Hard
+    }
+
+    // how far the ball can be from the paddle centre before the paddle moves, keeps it from
+    // jittering and makes weaker opponents sloppier
+    fn tracking_slack(&self) -> f32 {
+        match self {
+            Difficulty::Easy => util::PLAYER_SPEED * 3.0,
+            Difficulty::Normal => util::PLAYER_SPEED,
+            Difficulty::Hard => util::PLAYER_SPEED * 0.5,
+        }
+    }
+}
+
+// a simple opponent that holds up or down the way a player would
+#[derive(Debug, Copy, Clone)]
+pub struct Ai {
+    pub player: usize,
+    pub difficulty: Difficulty,
+    ticks: u32,
+}
+
+impl Ai {
+    pub fn new(player: usize, difficulty: Difficulty) -> Self {
+        Self {
+            player,
+            difficulty,
+            ticks: 0,
+        }
+    }
+
+    pub fn action(&mut self, state: &State) -> PaddleAction {
+        self.ticks = self.ticks.wrapping_add(1);
+        if !self.ticks.is_multiple_of(self.difficulty.move_every()) {
+            return PaddleAction::Stay;
+        }
+
+        let paddle = if self.player == 0 {
+            &state.player1
+        } else {
+            &state.player2
+        };
+
+        // follow the ball once it comes close enough, otherwise drift back to the middle
+        let ball = state.ball.position();
+        let incoming = (state.ball.velocity.x < 0.0) == (self.player == 0);
+        let distance = (ball.x - paddle.position().x).abs();
+        let tracking = incoming && distance < self.difficulty.reaction_distance();
+        let target = if !tracking {
+            0.0
+        } else if self.difficulty.predicts() {
+            util::predict_crossing(&state.ball, &state.arena, paddle.position().x)
+                .map_or(ball.y, |crossing| crossing.position.y)
+        } else {
+            ball.y
+        };
+
+        let offset = target - paddle.position().y;
+        let slack = self.difficulty.tracking_slack();
+        if offset > slack {
+            PaddleAction::Up
+        } else if offset < -slack {
+            PaddleAction::Down
+        } else {
+            PaddleAction::Stay
+        }
+    }
+
+    pub fn update(&mut self, state: &State, input: &mut Input) {
+        let (up, down) = if self.player == 0 {
+            (Action::P1Up, Action::P1Down)
+        } else {
+            (Action::P2Up, Action::P2Down)
+        };
+        let action = self.action(state);
+        input.set(up, action == PaddleAction::Up);
+        input.set(down, action == PaddleAction::Down);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::arena::{self, Arena};
+use crate::event::Event;
+use crate::raster::Raster;
+use crate::render::{RenderSink, RenderText};
+use crate::replay::Replay;
+use crate::world::{COURT_HALF_HEIGHT, COURT_HALF_WIDTH};
+
+use std::collections::BTreeMap;
+
+// the contact ratio where the ball meets the end of a paddle, see util::contact_ratio
+const PADDLE_EDGE_CONTACT: f32 = 0.25;
+
+// the heatmap splits each paddle and goal line into this many rows, top first
+const BINS: usize = 20;
+const BIN_HEIGHT: u32 = 12;
+const COLUMN_WIDTH: u32 = 120;
+const MARGIN: u32 = 16;
+const LABEL_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone)]
+pub struct Hit {
+    // position of the replay in the list it was analysed from
+    pub game: usize,
+    pub tick: u64,
+    pub player: u32,
+    pub contact: f32,
+    pub y: f32,
+    pub speed: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Goal {
+    pub game: usize,
+    pub tick: u64,
+    pub scorer: u32,
+    // where the ball crossed the goal line
+    pub y: f32,
+}
+
+// where recorded matches were won and lost: where the ball met each paddle, where it crossed
+// the goal lines and how long the rallies ran
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub games: usize,
+    pub hits: Vec<Hit>,
+    pub goals: Vec<Goal>,
+    // paddle hits in each rally that ended in a point
+    pub rallies: Vec<u32>,
+}
+
+impl Analysis {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // plays the replay back and adds what happened in it
+    pub fn add_replay(&mut self, replay: &Replay) {
+        let game = self.games;
+        self.games += 1;
+
+        let mut tick = 0;
+        let mut rally = 0;
+        // the ball before the tick, the ball is back in the middle by the time a score is seen
+        let mut ball = ((0.0, 0.0).into(), (0.0, 0.0).into());
+        replay.play_back(|env| {
+            for event in env.events() {
+                match *event {
+                    Event::Serve(_) => rally = 0,
+                    Event::PaddleHit {
+                        player,
+                        position,
+                        speed,
+                        contact,
+                    } => {
+                        rally += 1;
+                        self.hits.push(Hit {
+                            game,
+                            tick,
+                            player,
+                            contact,
+                            y: position.y,
+                            speed,
+                        });
+                    }
+                    Event::Score(scorer) => {
+                        self.rallies.push(rally);
+                        let goal = goal_line(&env.state().arena, scorer);
+                        self.goals.push(Goal {
+                            game,
+                            tick,
+                            scorer,
+                            y: goal_line_crossing(ball, goal),
+                        });
+                    }
+                    _ => (),
+                }
+            }
+            let state = env.state();
+            ball = (state.ball.position(), state.ball.velocity);
+            tick += 1;
+        });
+    }
+
+    pub fn hits_csv(&self) -> String {
+        let mut csv = String::from("game,tick,player,contact,y,speed\n");
+        for hit in &self.hits {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                hit.game + 1,
+                hit.tick,
+                hit.player + 1,
+                hit.contact,
+                hit.y,
+                hit.speed,
+            ));
+        }
+        csv
+    }
+
+    pub fn goals_csv(&self) -> String {
+        let mut csv = String::from("game,tick,scorer,y\n");
+        for goal in &self.goals {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                goal.game + 1,
+                goal.tick,
+                goal.scorer + 1,
+                goal.y,
+            ));
+        }
+        csv
+    }
+
+    // how many rallies ran to each number of hits
+    pub fn rallies_csv(&self) -> String {
+        let mut lengths = BTreeMap::new();
+        for hits in &self.rallies {
+            *lengths.entry(*hits).or_insert(0) += 1;
+        }
+        let mut csv = String::from("hits,rallies,share\n");
+        for (hits, rallies) in lengths {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                hits,
+                rallies,
+                rallies as f32 / self.rallies.len() as f32,
+            ));
+        }
+        csv
+    }
+
+    // four columns in court order: the goal line behind player 1, player 1's paddle, player 2's
+    // paddle and the goal line behind player 2. each is scaled to its own busiest row
+    pub fn heatmap(&self) -> Raster {
+        let map_height = BINS as u32 * BIN_HEIGHT;
+        let width = MARGIN * 5 + COLUMN_WIDTH * 4;
+        let height = MARGIN * 4 + map_height + LABEL_SIZE as u32 * 2;
+        let mut raster = Raster::new(width, height, (width as f32, height as f32));
+
+        let contact_row = |contact: f32| row(contact / PADDLE_EDGE_CONTACT);
+        let goal_row = |y: f32| row(y / COURT_HALF_HEIGHT);
+        let columns = [
+            (
+                "P1 goal",
+                "goals",
+                self.goals
+                    .iter()
+                    .filter(|goal| goal.scorer == 1)
+                    .map(|goal| goal_row(goal.y))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "P1 paddle",
+                "hits",
+                self.hits
+                    .iter()
+                    .filter(|hit| hit.player == 0)
+                    .map(|hit| contact_row(hit.contact))
+                    .collect(),
+            ),
+            (
+                "P2 paddle",
+                "hits",
+                self.hits
+                    .iter()
+                    .filter(|hit| hit.player == 1)
+                    .map(|hit| contact_row(hit.contact))
+                    .collect(),
+            ),
+            (
+                "P2 goal",
+                "goals",
+                self.goals
+                    .iter()
+                    .filter(|goal| goal.scorer == 0)
+                    .map(|goal| goal_row(goal.y))
+                    .collect(),
+            ),
+        ];
+
+        let map_top = MARGIN * 2 + LABEL_SIZE as u32;
+        for (i, (label, unit, rows)) in columns.iter().enumerate() {
+            let left = MARGIN + i as u32 * (COLUMN_WIDTH + MARGIN);
+            let mut counts = [0u32; BINS];
+            for row in rows {
+                counts[*row] += 1;
+            }
+            let busiest = counts.iter().copied().max().unwrap_or(0).max(1);
+            for (row, count) in counts.iter().enumerate() {
+                raster.fill(
+                    left as i64,
+                    (map_top + row as u32 * BIN_HEIGHT) as i64,
+                    COLUMN_WIDTH as i64,
+                    BIN_HEIGHT as i64,
+                    heat(*count as f32 / busiest as f32),
+                );
+            }
+
+            let center = (left + COLUMN_WIDTH / 2) as f32;
+            // text is centred on its position both ways
+            let label_middle = LABEL_SIZE * 0.5;
+            raster.push_text(&label_text(label, (center, MARGIN as f32 + label_middle)));
+            raster.push_text(&label_text(
+                &format!("{} {}", rows.len(), unit),
+                (
+                    center,
+                    (map_top + map_height + MARGIN) as f32 + label_middle,
+                ),
+            ));
+        }
+        raster
+    }
+}
+
+// the x of the line the scorer put the ball over and the opening in it, arenas score at the ends
+// of the court like the classic one but may only open part of each goal
+fn goal_line(arena: &Arena, scorer: u32) -> (f32, arena::Goal) {
+    if scorer == 0 {
+        (COURT_HALF_WIDTH, arena.right_goal)
+    } else {
+        (-COURT_HALF_WIDTH, arena.left_goal)
+    }
+}
+
+// the ball was at position with velocity on the tick before it scored
+fn goal_line_crossing(
+    (position, velocity): (cgmath::Vector2<f32>, cgmath::Vector2<f32>),
+    (line, goal): (f32, arena::Goal),
+) -> f32 {
+    let t = if velocity.x != 0.0 {
+        ((line - position.x) / velocity.x).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (position.y + velocity.y * t).clamp(goal.min_y, goal.max_y)
+}
+
+// -1 at the bottom to 1 at the top onto a heatmap row, 0 is the top row
+fn row(value: f32) -> usize {
+    let row = ((1.0 - value) * 0.5 * BINS as f32) as i64;
+    row.clamp(0, BINS as i64 - 1) as usize
+}
+
+// grey for rows nothing reached, then red through yellow to white as the row gets busier
+fn heat(t: f32) -> [u8; 4] {
+    if t <= 0.0 {
+        return [32, 32, 32, 255];
+    }
+    let channel = |start: f32| ((t * 3.0 - start).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(0.0), channel(1.0), channel(2.0), 255]
+}
+
+fn label_text(text: &str, position: (f32, f32)) -> RenderText {
+    RenderText {
+        text: String::from(text),
+        position: position.into(),
+        size: LABEL_SIZE,
+        centered: true,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::replay::{self, ReplayHeader};
+    use crate::tournament::{self, Entrant, TournamentConfig};
+
+    fn arena(name: &str) -> Arena {
+        Arena::builtin()
+            .into_iter()
+            .find(|arena| arena.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn counts_the_hits_goals_and_rallies_of_a_replay() {
+        let config = TournamentConfig {
+            points_to_win: 3,
+            arena: Some(String::from("Fortress")),
+            seed: 11,
+            ..Default::default()
+        };
+        let ai = Entrant::Ai(Difficulty::Normal);
+
+        // records the match and counts what happened in it while it's played
+        let mut recorded = None;
+        let (mut hits, mut rally, mut rallies) = (0, 0, Vec::new());
+        let result = tournament::play([&ai, &ai], &config, |env| {
+            let state = env.state();
+            recorded
+                .get_or_insert_with(|| Replay::new(ReplayHeader::new(state, config.seed)))
+                .frames
+                .push(replay::frame(state));
+            for event in env.events() {
+                match event {
+                    Event::Serve(_) => rally = 0,
+                    Event::PaddleHit { .. } => {
+                        hits += 1;
+                        rally += 1;
+                    }
+                    Event::Score(_) => rallies.push(rally),
+                    _ => (),
+                }
+            }
+        })
+        .unwrap();
+        let recorded = recorded.unwrap();
+
+        let mut analysis = Analysis::new();
+        analysis.add_replay(&recorded);
+        analysis.add_replay(&recorded);
+
+        assert_eq!(analysis.games, 2);
+        assert_eq!(analysis.hits.len(), hits * 2);
+        assert!(hits > 0);
+        assert_eq!(analysis.rallies, [rallies.clone(), rallies].concat());
+        for scorer in 0..2 {
+            let goals = analysis
+                .goals
+                .iter()
+                .filter(|goal| goal.game == 0 && goal.scorer == scorer);
+            assert_eq!(goals.count() as u32, result.score[scorer as usize]);
+        }
+        // fortress only opens the middle of each goal
+        for goal in &analysis.goals {
+            assert!(goal.y.abs() <= 0.6, "{:?}", goal);
+        }
+    }
+
+    #[test]
+    fn goals_are_placed_where_the_ball_crossed_the_line() {
+        let classic = arena("Classic");
+        let ball = |x: f32, y: f32, vx: f32, vy: f32| ((x, y).into(), (vx, vy).into());
+
+        // halfway through the tick, so halfway along its velocity
+        let right = ball(COURT_HALF_WIDTH - 0.01, 0.1, 0.02, 0.04);
+        let y = goal_line_crossing(right, goal_line(&classic, 0));
+        assert!((y - 0.12).abs() < 1e-5, "{}", y);
+
+        let left = ball(-COURT_HALF_WIDTH + 0.03, -0.2, -0.04, -0.08);
+        let y = goal_line_crossing(left, goal_line(&classic, 1));
+        assert!((y + 0.26).abs() < 1e-5, "{}", y);
+
+        // a ball that only scored by coming off a wall is kept inside the opening
+        let y = goal_line_crossing(
+            ball(COURT_HALF_WIDTH - 0.01, 0.59, 0.02, 0.04),
+            goal_line(&arena("Fortress"), 0),
+        );
+        assert_eq!(y, 0.6);
+    }
+}
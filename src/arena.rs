@@ -0,0 +1,252 @@
+use crate::ball::Ball;
+use crate::render::Quad;
+use crate::world::COURT_HALF_HEIGHT;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::path::Path;
+
+const BUILTIN_ARENAS: &[&str] = &[
+    include_str!("../res/arenas/classic.json"),
+    include_str!("../res/arenas/pillars.json"),
+    include_str!("../res/arenas/elevator.json"),
+    include_str!("../res/arenas/fortress.json"),
+];
+
+pub const ARENA_DIR: &str = "arenas";
+
+#[derive(Debug)]
+pub enum ArenaError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArenaError::Io(e) => write!(f, "could not read arena: {}", e),
+            ArenaError::Parse(e) => write!(f, "invalid arena: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArenaError {}
+
+// arena files are in world units, see world.rs for the size of the court
+#[derive(Debug, Deserialize)]
+struct ArenaFile {
+    name: String,
+    #[serde(default)]
+    walls: Vec<BlockFile>,
+    #[serde(default)]
+    obstacles: Vec<BlockFile>,
+    #[serde(default)]
+    goals: GoalsFile,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockFile {
+    position: (f32, f32),
+    size: (f32, f32),
+    #[serde(default)]
+    velocity: (f32, f32),
+    #[serde(default)]
+    range: (f32, f32),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GoalsFile {
+    left: Option<(f32, f32)>,
+    right: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub quad: Quad,
+    pub origin: cgmath::Vector2<f32>,
+    pub velocity: cgmath::Vector2<f32>,
+    pub range: cgmath::Vector2<f32>,
+}
+
+impl Block {
+    fn from_file(block: &BlockFile) -> Block {
+        Block {
+            quad: Quad::new(block.position.into(), block.size.into()),
+            origin: block.position.into(),
+            velocity: block.velocity.into(),
+            range: block.range.into(),
+        }
+    }
+
+    pub fn position(&self) -> cgmath::Vector2<f32> {
+        self.quad.position
+    }
+
+    pub fn size(&self) -> cgmath::Vector2<f32> {
+        self.quad.size
+    }
+
+    pub fn update_position(&mut self, position: cgmath::Vector2<f32>) {
+        self.quad = Quad::new(position, self.quad.size);
+    }
+
+    pub fn moves(&self) -> bool {
+        self.velocity.x != 0.0 || self.velocity.y != 0.0
+    }
+
+    // moves back and forth around the origin, turning around at the edge of its range
+    pub fn advance(&mut self) {
+        if !self.moves() {
+            return;
+        }
+
+        let position = self.position() + self.velocity;
+        let offset = position - self.origin;
+        if offset.x.abs() > self.range.x {
+            self.velocity.x *= -1.0;
+        }
+        if offset.y.abs() > self.range.y {
+            self.velocity.y *= -1.0;
+        }
+        self.update_position(position);
+    }
+
+    pub fn reset(&mut self) {
+        self.update_position(self.origin);
+    }
+
+    pub fn bounds(&self) -> (cgmath::Vector2<f32>, cgmath::Vector2<f32>) {
+        let radii = self.size() * 0.5;
+        (self.position() - radii, self.position() + radii)
+    }
+
+    pub fn contains(&self, ball: &Ball) -> bool {
+        let (min, max) = self.bounds();
+        let (b_min, b_max) = ball.bounds();
+
+        min.x < b_max.x && max.x > b_min.x && min.y < b_max.y && max.y > b_min.y
+    }
+
+    // pushes the ball out along the axis of least overlap and reflects it
+    pub fn bounce(&self, ball: &mut Ball) -> bool {
+        if !self.contains(ball) {
+            return false;
+        }
+
+        let delta = ball.position() - self.position();
+        let overlap_x = self.size().x * 0.5 + ball.radius() - delta.x.abs();
+        let overlap_y = self.size().y * 0.5 + ball.radius() - delta.y.abs();
+
+        let mut position = ball.position();
+        if overlap_x < overlap_y {
+            position.x += overlap_x * delta.x.signum();
+            if ball.velocity.x * delta.x < 0.0 {
+                ball.velocity.x *= -1.0;
+            }
+        } else {
+            position.y += overlap_y * delta.y.signum();
+            if ball.velocity.y * delta.y < 0.0 {
+                ball.velocity.y *= -1.0;
+            }
+        }
+        ball.update_position(position);
+        true
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+impl Goal {
+    fn from_file(opening: Option<(f32, f32)>) -> Goal {
+        let (min_y, max_y) = opening.unwrap_or((-COURT_HALF_HEIGHT, COURT_HALF_HEIGHT));
+        Goal { min_y, max_y }
+    }
+
+    pub fn contains(&self, y: f32) -> bool {
+        y >= self.min_y && y <= self.max_y
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arena {
+    pub name: String,
+    pub walls: Vec<Block>,
+    pub obstacles: Vec<Block>,
+    pub left_goal: Goal,
+    pub right_goal: Goal,
+    #[serde(skip)]
+    pub visible: bool,
+}
+
+impl Arena {
+    pub fn classic() -> Arena {
+        Arena::parse(BUILTIN_ARENAS[0]).unwrap()
+    }
+
+    pub fn parse(source: &str) -> Result<Arena, ArenaError> {
+        let file: ArenaFile = serde_json::from_str(source).map_err(ArenaError::Parse)?;
+        Ok(Arena {
+            name: file.name,
+            walls: file.walls.iter().map(Block::from_file).collect(),
+            obstacles: file.obstacles.iter().map(Block::from_file).collect(),
+            left_goal: Goal::from_file(file.goals.left),
+            right_goal: Goal::from_file(file.goals.right),
+            visible: false,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Arena, ArenaError> {
+        let source = std::fs::read_to_string(path).map_err(ArenaError::Io)?;
+        Arena::parse(&source)
+    }
+
+    pub fn builtin() -> Vec<Arena> {
+        BUILTIN_ARENAS
+            .iter()
+            .map(|source| Arena::parse(source).unwrap())
+            .collect()
+    }
+
+    // the built in arenas followed by any *.json files found in the arena directory
+    pub fn load_all() -> Vec<Arena> {
+        let mut arenas = Arena::builtin();
+
+        let mut paths: Vec<_> = match std::fs::read_dir(ARENA_DIR) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension() == Some("json".as_ref()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        for path in paths {
+            match Arena::load(&path) {
+                Ok(arena) => arenas.push(arena),
+                Err(e) => eprintln!("skipping {}: {}", path.display(), e),
+            }
+        }
+
+        arenas
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.walls.iter().chain(self.obstacles.iter())
+    }
+
+    pub fn advance(&mut self) {
+        for obstacle in &mut self.obstacles {
+            obstacle.advance();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for obstacle in &mut self.obstacles {
+            obstacle.reset();
+        }
+    }
+}
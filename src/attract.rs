@@ -0,0 +1,76 @@
+use crate::ai::{Ai, Difficulty};
+use crate::arena::Arena;
+use crate::event::Event;
+use crate::input::Input;
+use crate::render::RenderSink;
+use crate::simulation::Simulation;
+use crate::state::{GameState, State};
+use crate::system::*;
+
+use std::time::Duration;
+
+// how long the main menu has to sit untouched before the demo starts
+pub const ATTRACT_DELAY: Duration = Duration::from_secs(15);
+
+// an ai versus ai match that plays behind the main menu, it has its own state so the real
+// match, stats and records are never touched
+pub struct AttractMode {
+    state: State,
+    input: Input,
+    events: Vec<Event>,
+    ais: [Ai; 2],
+    simulation: Simulation,
+    visibility_system: VisibilitySystem,
+}
+
+impl AttractMode {
+    pub fn new(arenas: Vec<Arena>, arena_index: usize, window_size: (f32, f32)) -> Self {
+        let mut state = State::new(arenas, false);
+        state.resize(window_size);
+        state.select_arena(arena_index);
+
+        let mut simulation = Simulation::new();
+        simulation.serve(&mut state);
+
+        Self {
+            state,
+            input: Input::new(),
+            events: Vec::new(),
+            ais: [
+                Ai::new(0, Difficulty::Normal),
+                Ai::new(1, Difficulty::Normal),
+            ],
+            simulation,
+            visibility_system: VisibilitySystem,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.events.clear();
+        for ai in self.ais.iter_mut() {
+            ai.update(&self.state, &mut self.input);
+        }
+
+        self.visibility_system
+            .update_state(&mut self.input, &mut self.state, &mut self.events);
+        self.simulation
+            .step(&mut self.input, &mut self.state, &mut self.events);
+        self.input.end_frame();
+
+        // the demo never ends, a finished match just starts over
+        if self.state.game_state == GameState::GameOver {
+            self.state.player1.score = 0;
+            self.state.player2.score = 0;
+            self.simulation.serve(&mut self.state);
+        }
+    }
+
+    pub fn resize(&mut self, window_size: (f32, f32)) {
+        self.state.resize(window_size);
+    }
+
+    pub fn draw(&mut self, sink: &mut impl RenderSink) {
+        self.state.refresh_scores();
+        self.state.draw(sink);
+    }
+}
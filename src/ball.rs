@@ -1,8 +1,12 @@
-use dynamo_lib::geometry::quad::Quad;
+use crate::render::Quad;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ball {
     pub quad: Quad,
     pub velocity: cgmath::Vector2<f32>,
+    // set every frame by the visibility system, so it isn't saved
+    #[serde(skip)]
     pub visible: bool,
 }
 
@@ -23,6 +27,14 @@ impl Ball {
         self.quad.size.x
     }
 
+    pub fn bounds(&self) -> (cgmath::Vector2<f32>, cgmath::Vector2<f32>) {
+        let radii = cgmath::Vector2 {
+            x: self.radius(),
+            y: self.radius(),
+        };
+        (self.position() - radii, self.position() + radii)
+    }
+
     pub fn update_position(&mut self, position: cgmath::Vector2<f32>) {
         self.quad = Quad::new(position, self.quad.size);
     }
@@ -0,0 +1,79 @@
+use crate::env::{Observation, PaddleAction, PongEnv, OBSERVATION_SIZE};
+use rayon::prelude::*;
+
+#[derive(Debug, Copy, Clone)]
+pub struct StepResult {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+}
+
+impl Default for StepResult {
+    fn default() -> Self {
+        Self {
+            observation: [0.0; OBSERVATION_SIZE],
+            reward: 0.0,
+            done: false,
+        }
+    }
+}
+
+// steps many independent matches at once across the rayon thread pool. the caller owns the
+// action and result buffers so a tick allocates nothing, and nothing here ever renders
+pub struct BatchEnv {
+    envs: Vec<PongEnv>,
+}
+
+impl BatchEnv {
+    pub fn new(count: usize) -> Self {
+        Self {
+            envs: (0..count).into_par_iter().map(|_| PongEnv::new()).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    // match i is seeded with seed + i so a batch is reproducible as a whole
+    pub fn reset(&mut self, seed: u64, observations: &mut [Observation]) {
+        assert_eq!(observations.len(), self.envs.len());
+        self.envs
+            .par_iter_mut()
+            .zip(observations.par_iter_mut())
+            .enumerate()
+            .for_each(|(i, (env, observation))| {
+                *observation = env.reset(seed.wrapping_add(i as u64));
+            });
+    }
+
+    pub fn reset_one(&mut self, index: usize, seed: u64) -> Observation {
+        self.envs[index].reset(seed)
+    }
+
+    // finished matches keep reporting done until they're reset
+    pub fn step(&mut self, actions: &[(PaddleAction, PaddleAction)], results: &mut [StepResult]) {
+        assert_eq!(actions.len(), self.envs.len());
+        assert_eq!(results.len(), self.envs.len());
+        self.envs
+            .par_iter_mut()
+            .zip(actions.par_iter())
+            .zip(results.par_iter_mut())
+            .for_each(|((env, (p1, p2)), result)| {
+                let (observation, reward, done) = env.step(*p1, *p2);
+                *result = StepResult {
+                    observation,
+                    reward,
+                    done,
+                };
+            });
+    }
+
+    pub fn env(&self, index: usize) -> &PongEnv {
+        &self.envs[index]
+    }
+}
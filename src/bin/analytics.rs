@@ -0,0 +1,74 @@
+use pong::analytics::Analysis;
+use pong::replay::Replay;
+
+use std::process;
+
+const USAGE: &str = "usage: analytics [--output PREFIX] REPLAY...
+
+reads replays recorded with pong --record and writes
+  PREFIX-hits.csv       where each paddle hit landed on the paddle
+  PREFIX-goals.csv      where each goal crossed the goal line
+  PREFIX-rallies.csv    how many rallies ran to each number of hits
+  PREFIX-heatmap.png    paddle contacts and goal crossings for both players
+PREFIX defaults to analytics";
+
+fn main() {
+    let mut output = String::from("analytics");
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--output" => {
+                output = args
+                    .next()
+                    .unwrap_or_else(|| fail(&format!("{} needs a value", arg)))
+            }
+            path => paths.push(String::from(path)),
+        }
+    }
+    if paths.is_empty() {
+        fail("need at least one replay");
+    }
+
+    let mut analysis = Analysis::new();
+    for path in &paths {
+        match Replay::load(path) {
+            Ok(replay) => analysis.add_replay(&replay),
+            Err(e) => fail(&format!("{}: {}", path, e)),
+        }
+    }
+
+    for (path, contents) in [
+        (format!("{}-hits.csv", output), analysis.hits_csv()),
+        (format!("{}-goals.csv", output), analysis.goals_csv()),
+        (format!("{}-rallies.csv", output), analysis.rallies_csv()),
+    ]
+    .iter()
+    {
+        if let Err(e) = std::fs::write(path, contents) {
+            fail(&format!("could not write {}: {}", path, e));
+        }
+    }
+    let heatmap = format!("{}-heatmap.png", output);
+    if let Err(e) = analysis.heatmap().save_png(&heatmap) {
+        fail(&format!("{}: {}", heatmap, e));
+    }
+
+    println!(
+        "{} games, {} hits, {} goals, {} rallies",
+        analysis.games,
+        analysis.hits.len(),
+        analysis.goals.len(),
+        analysis.rallies.len()
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
@@ -0,0 +1,54 @@
+use pong::arena::Arena;
+use pong::bot::{self, Bot};
+use pong::save::SavedMatch;
+use pong::session::Session;
+use pong::tournament::Entrant;
+
+use std::process;
+
+const USAGE: &str = "usage: pong-tui [--p1 PLAYER] [--p2 PLAYER]
+
+players are human, ai:easy, ai:normal, ai:hard or bot:COMMAND, humans use the keyboard.
+give both an ai or bot to watch, for example
+  pong-tui --p1 ai:hard --p2 \"bot:python3 'my bot.py'\"";
+
+fn main() {
+    let mut session = Session::new(Arena::load_all(), SavedMatch::exists());
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let player = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--p1" => 0,
+            "--p2" => 1,
+            _ => fail(&format!("unknown argument {}", arg)),
+        };
+        let spec = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("{} needs a value", arg)));
+        match Entrant::parse(&spec) {
+            _ if spec == "human" => (),
+            Some(Entrant::Ai(difficulty)) => session.set_ai(player, difficulty),
+            Some(Entrant::Bot(command)) => {
+                match Bot::from_command(&command, bot::DEFAULT_TIMEOUT) {
+                    Ok(bot) => session.set_bot(player, bot),
+                    Err(e) => fail(&format!("{}: {}", arg, e)),
+                }
+            }
+            None => fail(&format!("invalid player {}", spec)),
+        }
+    }
+
+    if let Err(e) = pong::tui::run(session) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
@@ -0,0 +1,88 @@
+use pong::state::MAX_POINTS_TO_WIN;
+use pong::tournament::{self, Entrant, Format, TournamentConfig};
+
+use std::process;
+
+const USAGE: &str = "usage: tournament [--format round-robin|knockout] [--points N] [--arena NAME]
+                  [--seed N] [--max-ticks N] [--output PREFIX] ENTRANT ENTRANT...
+
+entrants are ai:easy, ai:normal, ai:hard or bot:COMMAND, for example
+  tournament ai:hard \"bot:python3 my_bot.py\"
+
+writes PREFIX-matches.csv, PREFIX-standings.csv and PREFIX.json, PREFIX defaults to tournament";
+
+fn main() {
+    let mut config = TournamentConfig::default();
+    let mut output = String::from("tournament");
+    let mut entrants = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--format" => {
+                config.format = Format::from_name(&value(&mut args, &arg))
+                    .unwrap_or_else(|| fail("unknown format"));
+            }
+            "--points" => {
+                config.points_to_win = number(&mut args, &arg);
+                if !(1..=MAX_POINTS_TO_WIN).contains(&config.points_to_win) {
+                    fail(&format!(
+                        "--points must be between 1 and {}, not {}",
+                        MAX_POINTS_TO_WIN, config.points_to_win
+                    ));
+                }
+            }
+            "--arena" => config.arena = Some(value(&mut args, &arg)),
+            "--seed" => config.seed = number(&mut args, &arg),
+            "--max-ticks" => config.max_ticks = number(&mut args, &arg),
+            "--output" => output = value(&mut args, &arg),
+            spec => match Entrant::parse(spec) {
+                Some(entrant) => entrants.push(entrant),
+                None => fail(&format!("invalid entrant {}", spec)),
+            },
+        }
+    }
+    if entrants.len() < 2 {
+        fail("need at least two entrants");
+    }
+
+    let result = tournament::run(&entrants, &config).unwrap_or_else(|e| fail(&e.to_string()));
+
+    let json = result
+        .to_json()
+        .unwrap_or_else(|e| fail(&format!("could not write results: {}", e)));
+    for (path, contents) in [
+        (format!("{}-matches.csv", output), result.matches_csv()),
+        (format!("{}-standings.csv", output), result.standings_csv()),
+        (format!("{}.json", output), json),
+    ]
+    .iter()
+    {
+        if let Err(e) = std::fs::write(path, contents) {
+            fail(&format!("could not write {}: {}", path, e));
+        }
+    }
+
+    print!("{}", result.standings_csv());
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} needs a value", flag)))
+}
+
+fn number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> T {
+    let value = value(args, flag);
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("{} needs a whole number, not {}", flag, value)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
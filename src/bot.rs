@@ -0,0 +1,382 @@
+use crate::env::PaddleAction;
+use crate::input::{Action, Input};
+use crate::state::{GameState, State};
+use serde::Serialize;
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+// how long a match without a window waits for each answer, see Bot::act_waiting
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+// this many unanswered messages, or invalid answers in a row, and the bot is dropped
+const MAX_MISSED: u32 = 60;
+
+#[derive(Debug)]
+pub enum BotError {
+    Spawn(std::io::Error),
+    Io(std::io::Error),
+    Exited,
+    Hung,
+    InvalidCommand(String),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BotError::Spawn(e) => write!(f, "could not start bot: {}", e),
+            BotError::Io(e) => write!(f, "could not talk to bot: {}", e),
+            BotError::Exited => write!(f, "bot exited"),
+            BotError::Hung => write!(f, "bot stopped answering"),
+            BotError::InvalidCommand(line) => write!(f, "bot sent an invalid command: {:?}", line),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+#[derive(Debug, Serialize)]
+pub struct BotBall {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+}
+
+// one line of json per tick on the bot's stdin, the bot answers with a line reading up, down or
+// stay. player is the paddle the bot controls, 0 on the left and 1 on the right
+#[derive(Debug, Serialize)]
+pub struct BotMessage {
+    pub tick: u64,
+    pub player: u32,
+    pub game_state: GameState,
+    pub ball: BotBall,
+    pub paddles: [(f32, f32); 2],
+    pub scores: [u32; 2],
+}
+
+impl BotMessage {
+    pub fn from_state(state: &State, player: u32, tick: u64) -> Self {
+        Self {
+            tick,
+            player,
+            game_state: state.game_state,
+            ball: BotBall {
+                position: state.ball.position().into(),
+                velocity: state.ball.velocity.into(),
+            },
+            paddles: [
+                state.player1.position().into(),
+                state.player2.position().into(),
+            ],
+            scores: [state.player1.score, state.player2.score],
+        }
+    }
+}
+
+// what the reader thread makes of each line, invalid lines are kept to report them
+type Reply = Result<PaddleAction, String>;
+
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<Reply>,
+    timeout: Duration,
+    tick: u64,
+    // answers still owed for messages already sent, late answers are skipped over
+    pending: u32,
+    // invalid lines in a row
+    invalid: u32,
+    last: PaddleAction,
+    buffer: Vec<u8>,
+}
+
+impl Bot {
+    pub fn spawn(program: &str, args: &[String], timeout: Duration) -> Result<Self, BotError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(BotError::Spawn)?;
+        let stdin = child.stdin.take().ok_or(BotError::Exited)?;
+        let stdout = child.stdout.take().ok_or(BotError::Exited)?;
+
+        // reads happen on their own thread so a silent bot can't block the game
+        let (sender, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(parse_reply(line)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+            timeout,
+            tick: 0,
+            pending: 0,
+            invalid: 0,
+            last: PaddleAction::Stay,
+            buffer: Vec::new(),
+        })
+    }
+
+    // parses a command line such as "python3 'my bot.py' --level 2", quoted the way a shell would
+    pub fn from_command(command: &str, timeout: Duration) -> Result<Self, BotError> {
+        let invalid = |message| {
+            BotError::Spawn(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ))
+        };
+        let mut parts = split_command(command)
+            .ok_or_else(|| invalid("unterminated quote in bot command"))?
+            .into_iter();
+        let program = parts.next().ok_or_else(|| invalid("empty bot command"))?;
+        let args: Vec<String> = parts.collect();
+        Self::spawn(&program, &args, timeout)
+    }
+
+    // sends the bot the state and plays the last move it sent without waiting for an answer, a
+    // bot slower than the game plays a tick or more behind
+    pub fn act(&mut self, state: &State, player: u32) -> Result<PaddleAction, BotError> {
+        self.send(state, player)?;
+        while self.pending > 0 {
+            match self.replies.try_recv() {
+                Ok(reply) => self.receive(reply)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err(BotError::Exited),
+            }
+        }
+        self.check_hung()?;
+        Ok(self.last)
+    }
+
+    // like act, but gives the bot up to its timeout to answer this tick. for matches played
+    // without a window, where nothing is kept waiting and the game would outrun the bot
+    pub fn act_waiting(&mut self, state: &State, player: u32) -> Result<PaddleAction, BotError> {
+        self.send(state, player)?;
+        let deadline = Instant::now() + self.timeout;
+        while self.pending > 0 {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.replies.recv_timeout(timeout) {
+                Ok(reply) => self.receive(reply)?,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Err(BotError::Exited),
+            }
+        }
+        self.check_hung()?;
+        Ok(self.last)
+    }
+
+    fn send(&mut self, state: &State, player: u32) -> Result<(), BotError> {
+        self.tick += 1;
+        let message = BotMessage::from_state(state, player, self.tick);
+        self.buffer.clear();
+        // the message only holds numbers and enum names, it can't fail to serialize
+        let _ = serde_json::to_writer(&mut self.buffer, &message);
+        self.buffer.push(b'\n');
+        if let Err(e) = self.stdin.write_all(&self.buffer) {
+            return Err(match e.kind() {
+                std::io::ErrorKind::BrokenPipe => BotError::Exited,
+                _ => BotError::Io(e),
+            });
+        }
+        self.stdin.flush().map_err(BotError::Io)?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    // an invalid line still answers a message, the bot keeps its last move for it
+    fn receive(&mut self, reply: Reply) -> Result<(), BotError> {
+        self.pending = self.pending.saturating_sub(1);
+        match reply {
+            Ok(action) => {
+                self.last = action;
+                self.invalid = 0;
+            }
+            Err(line) => {
+                self.invalid += 1;
+                if self.invalid > MAX_MISSED {
+                    return Err(BotError::InvalidCommand(line));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_hung(&self) -> Result<(), BotError> {
+        if self.pending > MAX_MISSED {
+            Err(BotError::Hung)
+        } else {
+            Ok(())
+        }
+    }
+
+    // drives the bot's paddle through the same input the keyboard uses
+    pub fn update(
+        &mut self,
+        state: &State,
+        player: u32,
+        input: &mut Input,
+    ) -> Result<(), BotError> {
+        let (up, down) = if player == 0 {
+            (Action::P1Up, Action::P1Down)
+        } else {
+            (Action::P2Up, Action::P2Down)
+        };
+        let action = match self.act(state, player) {
+            Ok(action) => action,
+            Err(e) => {
+                input.set(up, false);
+                input.set(down, false);
+                return Err(e);
+            }
+        };
+        input.set(up, action == PaddleAction::Up);
+        input.set(down, action == PaddleAction::Down);
+        Ok(())
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_reply(line: String) -> Reply {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "up" => Ok(PaddleAction::Up),
+        "down" => Ok(PaddleAction::Down),
+        "stay" | "" => Ok(PaddleAction::Stay),
+        _ => Err(line),
+    }
+}
+
+// splits a command into words at unquoted whitespace. single quotes keep everything in them,
+// double quotes and a backslash outside quotes keep the next character. None when a quote isn't
+// closed
+fn split_command(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => word.push(chars.next()?),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+
+    fn words(command: &str) -> Vec<String> {
+        split_command(command).unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            words("  python3 bot.py\t--level 2 "),
+            ["python3", "bot.py", "--level", "2"]
+        );
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn keeps_quoted_words_together() {
+        assert_eq!(words("python3 'my bot.py'"), ["python3", "my bot.py"]);
+        assert_eq!(
+            words("python3 \"my bot.py\" -v"),
+            ["python3", "my bot.py", "-v"]
+        );
+        assert_eq!(words("python3 my\\ bot.py"), ["python3", "my bot.py"]);
+        assert_eq!(words("run --name=\"a b\"''"), ["run", "--name=a b"]);
+        assert_eq!(words("echo '' x"), ["echo", "", "x"]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(split_command("python3 'my bot.py").is_none());
+        assert!(split_command("python3 \"my bot.py").is_none());
+        assert!(split_command("python3 bot.py\\").is_none());
+    }
+
+    #[test]
+    fn act_does_not_wait_for_an_answer() {
+        let mut bot = Bot::spawn("sleep", &[String::from("5")], Duration::from_secs(5)).unwrap();
+        let state = State::new(Arena::builtin(), false);
+        let started = Instant::now();
+        for _ in 0..MAX_MISSED {
+            assert_eq!(bot.act(&state, 0).unwrap(), PaddleAction::Stay);
+        }
+        assert!(matches!(bot.act(&state, 0), Err(BotError::Hung)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn invalid_lines_count_against_the_limit() {
+        // answers the first message with nonsense and every other one with up
+        let script = "read l; echo jump; while read l; do echo up; done";
+        let mut bot = Bot::spawn(
+            "sh",
+            &[String::from("-c"), String::from(script)],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let state = State::new(Arena::builtin(), false);
+        assert_eq!(bot.act_waiting(&state, 0).unwrap(), PaddleAction::Stay);
+        assert_eq!(bot.act_waiting(&state, 0).unwrap(), PaddleAction::Up);
+
+        let script = "while read l; do echo jump; done";
+        let mut bot = Bot::spawn(
+            "sh",
+            &[String::from("-c"), String::from(script)],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        for _ in 0..MAX_MISSED {
+            assert_eq!(bot.act_waiting(&state, 0).unwrap(), PaddleAction::Stay);
+        }
+        assert!(matches!(
+            bot.act_waiting(&state, 0),
+            Err(BotError::InvalidCommand(_))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::arena::Arena;
+use crate::input::Input;
+use crate::raster::{ExportError, GifWriter, Raster};
+use crate::replay::Replay;
+use crate::state::{GameState, State};
+use crate::system::{System, VisibilitySystem};
+use crate::util;
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CAPTURE_DIR: &str = "captures";
+// the longest side of a capture in pixels, larger windows are scaled down to keep gifs small
+const CAPTURE_SIZE: f32 = 640.0;
+// recordings keep one tick in this many, 30 frames a second
+const RECORD_EVERY: u32 = 2;
+// the window a replay is drawn for when there isn't one, scaled down to CAPTURE_SIZE
+const REPLAY_WINDOW_SIZE: (f32, f32) = (1280.0, 720.0);
+
+struct Recording {
+    writer: GifWriter<BufWriter<File>>,
+    path: PathBuf,
+    ticks: u32,
+}
+
+// screenshots and gif recordings of whatever the game draws, rendered on the cpu
+#[derive(Default)]
+pub struct Capture {
+    raster: Option<Raster>,
+    recording: Option<Recording>,
+    screenshot: bool,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn request_screenshot(&mut self) {
+        self.screenshot = true;
+    }
+
+    pub fn toggle_recording(&mut self, window_size: (f32, f32)) {
+        match self.recording.take() {
+            // dropping the writer finishes the file
+            Some(recording) => eprintln!("saved {}", recording.path.display()),
+            None => match self.start_recording(window_size) {
+                Ok(recording) => self.recording = Some(recording),
+                Err(e) => eprintln!("{}", e),
+            },
+        }
+    }
+
+    fn start_recording(&mut self, window_size: (f32, f32)) -> Result<Recording, ExportError> {
+        let raster = self.raster(window_size);
+        let (width, height) = (raster.width(), raster.height());
+        let path = capture_path("recording", "gif")?;
+        let writer = gif_writer(&path, width, height)?;
+        Ok(Recording {
+            writer,
+            path,
+            ticks: 0,
+        })
+    }
+
+    // a blank raster to draw this tick's frame into, None when nothing needs one
+    pub fn frame(&mut self, window_size: (f32, f32)) -> Option<&mut Raster> {
+        let recording_frame = match &mut self.recording {
+            Some(recording) => {
+                recording.ticks += 1;
+                (recording.ticks - 1).is_multiple_of(RECORD_EVERY)
+            }
+            None => false,
+        };
+        if !self.screenshot && !recording_frame {
+            return None;
+        }
+        Some(self.raster(window_size))
+    }
+
+    // saves the frame drawn into the raster handed out by frame
+    pub fn finish_frame(&mut self) {
+        let raster = match &self.raster {
+            Some(raster) => raster,
+            None => return,
+        };
+
+        if self.screenshot {
+            self.screenshot = false;
+            let result = capture_path("screenshot", "png").and_then(|path| {
+                raster.save_png(&path)?;
+                Ok(path)
+            });
+            match result {
+                Ok(path) => eprintln!("saved {}", path.display()),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
+        let failed = match &mut self.recording {
+            Some(recording) if (recording.ticks - 1).is_multiple_of(RECORD_EVERY) => {
+                recording.writer.push(raster).err()
+            }
+            _ => None,
+        };
+        if let Some(e) = failed {
+            eprintln!("{}", e);
+            self.recording = None;
+        }
+    }
+
+    // the raster keeps its size while recording so every frame of the gif matches
+    fn raster(&mut self, window_size: (f32, f32)) -> &mut Raster {
+        let (width, height) = capture_size(window_size);
+        let recording = self.recording.is_some();
+        let raster = match self.raster.take() {
+            Some(raster) if recording || (raster.width(), raster.height()) == (width, height) => {
+                raster
+            }
+            _ => Raster::new(width, height, window_size),
+        };
+        let raster = self.raster.get_or_insert(raster);
+        raster.reset(window_size);
+        raster
+    }
+}
+
+// plays a replay back without a window and saves it to path, a .gif of the whole match or a
+// .png of its last frame
+pub fn capture_replay(replay: &Replay, path: &Path) -> Result<(), ExportError> {
+    let animated = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gif") => true,
+        Some("png") => false,
+        _ => return Err(ExportError::Format(path.to_path_buf())),
+    };
+    let (width, height) = capture_size(REPLAY_WINDOW_SIZE);
+    let mut raster = Raster::new(width, height, REPLAY_WINDOW_SIZE);
+    let mut writer = match animated {
+        true => Some(gif_writer(path, width, height)?),
+        false => None,
+    };
+
+    // the env's state is never laid out for a window, so the match is copied into one that is
+    let mut view = State::new(Arena::builtin(), false);
+    view.resize(REPLAY_WINDOW_SIZE);
+    let mut ticks: u32 = 0;
+    let mut failed = None;
+    replay.play_back(|env| {
+        let state = env.state();
+        view.player1 = state.player1.clone();
+        view.player2 = state.player2.clone();
+        view.ball = state.ball.clone();
+        view.arena = state.arena.clone();
+        // the last tick shows the court rather than the game over menu
+        view.game_state = match state.game_state {
+            GameState::Serving => GameState::Serving,
+            _ => GameState::Playing,
+        };
+        ticks += 1;
+
+        let writer = match &mut writer {
+            Some(writer) if failed.is_none() && (ticks - 1).is_multiple_of(RECORD_EVERY) => writer,
+            _ => return,
+        };
+        draw(&mut view, &mut raster);
+        failed = writer.push(&raster).err();
+    });
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    if !animated {
+        draw(&mut view, &mut raster);
+        raster.save_png(path)?;
+    }
+    Ok(())
+}
+
+fn draw(view: &mut State, raster: &mut Raster) {
+    VisibilitySystem.update_state(&mut Input::new(), view, &mut Vec::new());
+    view.refresh_scores();
+    raster.reset(view.window_size());
+    view.draw(raster);
+}
+
+fn gif_writer(
+    path: &Path,
+    width: u32,
+    height: u32,
+) -> Result<GifWriter<BufWriter<File>>, ExportError> {
+    let frame_time = Duration::from_secs(RECORD_EVERY as u64) / util::TICKS_PER_SECOND;
+    GifWriter::new(
+        BufWriter::new(File::create(path)?),
+        width,
+        height,
+        frame_time,
+    )
+}
+
+fn capture_size((width, height): (f32, f32)) -> (u32, u32) {
+    let scale = (CAPTURE_SIZE / width.max(height)).min(1.0);
+    (
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    )
+}
+
+// captures/screenshot-1600000000000.png, named by the time in milliseconds
+fn capture_path(name: &str, extension: &str) -> Result<PathBuf, ExportError> {
+    fs::create_dir_all(CAPTURE_DIR)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    Ok(PathBuf::from(CAPTURE_DIR).join(format!("{}-{}.{}", name, millis, extension)))
+}
@@ -0,0 +1,164 @@
+use crate::render::{Quad, RenderSink, RenderText};
+use crate::state::State;
+use crate::world::Viewport;
+
+use std::time::{Duration, Instant};
+
+const LINE_WIDTH: f32 = 0.005;
+// how many ticks ahead the velocity vector reaches
+const VELOCITY_SCALE: f32 = 10.0;
+
+pub struct DebugOverlay {
+    pub enabled: bool,
+    last_frame: Instant,
+    tick_started: Instant,
+    frame_time: Duration,
+    tick_time: Duration,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            last_frame: Instant::now(),
+            tick_started: Instant::now(),
+            frame_time: Duration::from_secs(0),
+            tick_time: Duration::from_secs(0),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn begin_tick(&mut self) {
+        let now = Instant::now();
+        self.frame_time = now - self.last_frame;
+        self.last_frame = now;
+        self.tick_started = now;
+    }
+
+    pub fn end_tick(&mut self) {
+        self.tick_time = self.tick_started.elapsed();
+    }
+
+    pub fn draw(&self, state: &State, sink: &mut impl RenderSink) {
+        if !self.enabled {
+            return;
+        }
+
+        for (min, max) in [state.player1.bounds(), state.player2.bounds()].iter() {
+            push_outline(sink, &state.viewport, *min, *max);
+        }
+        if state.arena.visible {
+            for block in state.arena.blocks() {
+                let radii = block.size() * 0.5;
+                push_outline(
+                    sink,
+                    &state.viewport,
+                    block.position() - radii,
+                    block.position() + radii,
+                );
+            }
+        }
+        if state.ball.visible {
+            let (min, max) = state.ball.bounds();
+            push_outline(sink, &state.viewport, min, max);
+            push_line(
+                sink,
+                &state.viewport,
+                state.ball.position(),
+                state.ball.position() + state.ball.velocity * VELOCITY_SCALE,
+            );
+        }
+        let prediction = state.predicted_crossing();
+        if let Some(crossing) = &prediction {
+            let mut from = state.ball.position();
+            for &to in crossing.bounces.iter().chain(Some(&crossing.position)) {
+                push_line(sink, &state.viewport, from, to);
+                from = to;
+            }
+        }
+
+        let frame_ms = self.frame_time.as_secs_f32() * 1000.0;
+        let fps = if frame_ms > 0.0 {
+            1000.0 / frame_ms
+        } else {
+            0.0
+        };
+        let mut text = format!(
+            "state: {:?}\nframe: {:.2} ms ({:.0} fps)\ntick: {:.3} ms\nball: ({:.3}, {:.3}) velocity ({:.4}, {:.4})",
+            state.game_state,
+            frame_ms,
+            fps,
+            self.tick_time.as_secs_f32() * 1000.0,
+            state.ball.position().x,
+            state.ball.position().y,
+            state.ball.velocity.x,
+            state.ball.velocity.y,
+        );
+        if let Some(crossing) = &prediction {
+            text.push_str(&format!(
+                "\narrives: y {:.3} in {} ticks, {} bounces",
+                crossing.position.y,
+                crossing.ticks,
+                crossing.bounces.len(),
+            ));
+        }
+        sink.push_text(&RenderText {
+            position: (20.0, 60.0).into(),
+            color: (0.0, 1.0, 0.0, 1.0).into(),
+            text,
+            size: 16.0,
+            ..Default::default()
+        });
+    }
+}
+
+fn push_outline(
+    sink: &mut impl RenderSink,
+    viewport: &Viewport,
+    min: cgmath::Vector2<f32>,
+    max: cgmath::Vector2<f32>,
+) {
+    let center = (min + max) * 0.5;
+    let size = max - min;
+    sink.push_quad(&viewport.quad(&Quad::new(
+        (center.x, max.y).into(),
+        (size.x, LINE_WIDTH).into(),
+    )));
+    sink.push_quad(&viewport.quad(&Quad::new(
+        (center.x, min.y).into(),
+        (size.x, LINE_WIDTH).into(),
+    )));
+    sink.push_quad(&viewport.quad(&Quad::new(
+        (min.x, center.y).into(),
+        (LINE_WIDTH, size.y).into(),
+    )));
+    sink.push_quad(&viewport.quad(&Quad::new(
+        (max.x, center.y).into(),
+        (LINE_WIDTH, size.y).into(),
+    )));
+}
+
+// quads can't be rotated, so lines are drawn as a run of small dots
+fn push_line(
+    sink: &mut impl RenderSink,
+    viewport: &Viewport,
+    from: cgmath::Vector2<f32>,
+    to: cgmath::Vector2<f32>,
+) {
+    let delta = to - from;
+    let steps = ((delta.x.abs().max(delta.y.abs())) / LINE_WIDTH).ceil() as usize;
+    for i in 0..=steps {
+        let t = if steps == 0 {
+            0.0
+        } else {
+            i as f32 / steps as f32
+        };
+        sink.push_quad(&viewport.quad(&Quad::new(
+            from + delta * t,
+            (LINE_WIDTH, LINE_WIDTH).into(),
+        )));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::arena::Arena;
+use crate::event::Event;
+use crate::input::{Action, Input};
+use crate::simulation::Simulation;
+use crate::state::{GameState, State};
+use crate::stats::MatchStats;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// ball x, ball y, ball velocity x, ball velocity y, player 1 y, player 2 y, all in world units
+pub const OBSERVATION_SIZE: usize = 6;
+
+pub type Observation = [f32; OBSERVATION_SIZE];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PaddleAction {
+    Stay,
+    Up,
+    Down,
+}
+
+// a headless match for training agents, one step is one game tick with both players' actions.
+// rewards are from player 1's side: 1 when they score, -1 when player 2 does, the game is zero
+// sum so player 2's reward is the negation
+pub struct PongEnv {
+    state: State,
+    input: Input,
+    events: Vec<Event>,
+    simulation: Simulation,
+}
+
+impl PongEnv {
+    pub fn new() -> Self {
+        Self {
+            state: State::new(Arena::builtin(), false),
+            input: Input::new(),
+            events: Vec::new(),
+            simulation: Simulation::new(),
+        }
+    }
+
+    // starts a new match, the same seed always plays out the same way for the same actions
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.state.rng = StdRng::seed_from_u64(seed);
+        self.state.player1.score = 0;
+        self.state.player2.score = 0;
+        self.state.player1.update_y_position(0.0);
+        self.state.player2.update_y_position(0.0);
+        self.state.ball.update_position((0.0, 0.0).into());
+        self.state.arena.reset();
+        self.state.stats = MatchStats::new();
+        self.input = Input::new();
+        self.simulation.serve(&mut self.state);
+        self.observation()
+    }
+
+    pub fn step(&mut self, p1: PaddleAction, p2: PaddleAction) -> (Observation, f32, bool) {
+        if self.done() {
+            return (self.observation(), 0.0, true);
+        }
+
+        // the last step's edges are kept until now so input() can still show them
+        self.input.end_frame();
+        self.events.clear();
+        set_paddle(&mut self.input, p1, Action::P1Up, Action::P1Down);
+        set_paddle(&mut self.input, p2, Action::P2Up, Action::P2Down);
+
+        self.simulation
+            .step(&mut self.input, &mut self.state, &mut self.events);
+
+        let mut reward = 0.0;
+        for event in &self.events {
+            self.state.stats.record(event);
+            if let Event::Score(player) = event {
+                reward += if *player == 0 { 1.0 } else { -1.0 };
+            }
+        }
+
+        (self.observation(), reward, self.done())
+    }
+
+    pub fn observation(&self) -> Observation {
+        let ball = self.state.ball.position();
+        let velocity = self.state.ball.velocity;
+        [
+            ball.x,
+            ball.y,
+            velocity.x,
+            velocity.y,
+            self.state.player1.position().y,
+            self.state.player2.position().y,
+        ]
+    }
+
+    pub fn set_points_to_win(&mut self, points: u32) {
+        self.state.rules.points_to_win = points.max(1);
+    }
+
+    // returns false when there's no arena with that name
+    pub fn select_arena(&mut self, name: &str) -> bool {
+        match self
+            .state
+            .arenas
+            .iter()
+            .position(|arena| arena.name == name)
+        {
+            Some(index) => {
+                self.state.select_arena(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // moves both paddles straight to these heights, how a replay puts them where they were
+    pub fn set_paddles(&mut self, [p1, p2]: [f32; 2]) {
+        self.state.player1.update_y_position(p1);
+        self.state.player2.update_y_position(p2);
+    }
+
+    pub fn arena_name(&self) -> &str {
+        &self.state.arena.name
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // what happened during the last step
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    // the paddle keys the last step held down, pressed and released
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn done(&self) -> bool {
+        self.state.game_state == GameState::GameOver
+    }
+
+    pub fn score(&self) -> (u32, u32) {
+        (self.state.player1.score, self.state.player2.score)
+    }
+}
+
+impl Default for PongEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn set_paddle(input: &mut Input, action: PaddleAction, up: Action, down: Action) {
+    input.set(up, action == PaddleAction::Up);
+    input.set(down, action == PaddleAction::Down);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
+
+    // the ball's velocity as it's about to be served
+    fn serve(seed: u64) -> (f32, f32) {
+        let observation = PongEnv::new().reset(seed);
+        (observation[2], observation[3])
+    }
+
+    #[test]
+    fn the_seed_picks_the_serve() {
+        assert_eq!(serve(1), serve(1));
+        assert_ne!(serve(1), serve(2));
+
+        let (x, y) = serve(3);
+        assert!(y.atan2(x.abs()).abs() <= util::MAX_SERVE_ANGLE);
+    }
+}
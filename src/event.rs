@@ -0,0 +1,15 @@
+#[derive(Debug, Copy, Clone)]
+pub enum Event {
+    ButtonPressed,
+    FocusChanged,
+    BallBounce(cgmath::Vector2<f32>),
+    PaddleHit {
+        player: u32,
+        position: cgmath::Vector2<f32>,
+        speed: f32,
+        // util::contact_ratio at the moment of the hit
+        contact: f32,
+    },
+    Serve(u32),
+    Score(u32),
+}
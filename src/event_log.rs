@@ -0,0 +1,248 @@
+use crate::event::Event;
+use crate::input::{Action, Input};
+use crate::state::{GameState, State};
+use serde::Serialize;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// one line of the log, positions are in world units with the court from -1 to 1 on y
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogEvent {
+    PaddleHit {
+        player: u32,
+        x: f32,
+        y: f32,
+        speed: f32,
+        contact: f32,
+    },
+    // off the top and bottom walls, the back walls beside the goals or a block in the arena
+    Bounce {
+        x: f32,
+        y: f32,
+    },
+    Serve {
+        player: u32,
+    },
+    Score {
+        player: u32,
+        score: [u32; 2],
+    },
+    StateChange {
+        from: GameState,
+        to: GameState,
+    },
+    Input {
+        action: Action,
+        pressed: bool,
+    },
+    // a controller stick moving a paddle, from -1 to 1
+    Axis {
+        player: u32,
+        value: f32,
+    },
+    ButtonPressed,
+    FocusChanged,
+}
+
+#[derive(Serialize)]
+struct LogLine<'a> {
+    tick: u64,
+    // milliseconds since the unix epoch
+    timestamp: u64,
+    #[serde(flatten)]
+    event: &'a LogEvent,
+}
+
+// writes what happens each tick to a json lines file. inputs and state changes aren't events
+// the systems push, inputs come from the press and release edges and state changes from
+// comparing each tick with the one before
+pub struct EventLog {
+    writer: BufWriter<File>,
+    axes: [f32; 2],
+    game_state: GameState,
+}
+
+impl EventLog {
+    // game_state is what the game is doing before the first tick that's logged
+    pub fn create(path: impl AsRef<Path>, game_state: GameState) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            axes: [0.0; 2],
+            game_state,
+        })
+    }
+
+    // called once the tick's systems have run with the events they pushed, and before
+    // Input::end_frame clears the tick's presses and releases
+    pub fn log_tick(
+        &mut self,
+        tick: u64,
+        input: &Input,
+        events: &[Event],
+        state: &State,
+    ) -> io::Result<()> {
+        let mut entries = Vec::new();
+
+        for action in Action::ALL.iter().copied() {
+            let state = input.action_state(action);
+            // both edges in one tick is a tap when the action ends up released, otherwise it
+            // was let go and pressed again
+            let edges: &[bool] = match (state.just_pressed, state.just_released, state.held) {
+                (true, true, false) => &[true, false],
+                (true, true, true) => &[false, true],
+                (true, false, _) => &[true],
+                (false, true, _) => &[false],
+                (false, false, _) => &[],
+            };
+            entries.extend(
+                edges
+                    .iter()
+                    .map(|&pressed| LogEvent::Input { action, pressed }),
+            );
+        }
+        for (player, (last, value)) in self
+            .axes
+            .iter_mut()
+            .zip([input.p1_axis, input.p2_axis].iter())
+            .enumerate()
+        {
+            if last != value {
+                *last = *value;
+                entries.push(LogEvent::Axis {
+                    player: player as u32,
+                    value: *value,
+                });
+            }
+        }
+
+        let score = [state.player1.score, state.player2.score];
+        entries.extend(events.iter().map(|event| match *event {
+            Event::PaddleHit {
+                player,
+                position,
+                speed,
+                contact,
+            } => LogEvent::PaddleHit {
+                player,
+                x: position.x,
+                y: position.y,
+                speed,
+                contact,
+            },
+            Event::BallBounce(position) => LogEvent::Bounce {
+                x: position.x,
+                y: position.y,
+            },
+            Event::Serve(player) => LogEvent::Serve { player },
+            Event::Score(player) => LogEvent::Score { player, score },
+            Event::ButtonPressed => LogEvent::ButtonPressed,
+            Event::FocusChanged => LogEvent::FocusChanged,
+        }));
+
+        if self.game_state != state.game_state {
+            entries.push(LogEvent::StateChange {
+                from: self.game_state,
+                to: state.game_state,
+            });
+            self.game_state = state.game_state;
+        }
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        for event in &entries {
+            serde_json::to_writer(
+                &mut self.writer,
+                &LogLine {
+                    tick,
+                    timestamp,
+                    event,
+                },
+            )?;
+            self.writer.write_all(b"\n")?;
+        }
+        // the window can close without the game being dropped, so nothing waits in the buffer
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+
+    // tests run in parallel, so each names its own file
+    fn logged_inputs(name: &str, input: &Input) -> Vec<(String, bool)> {
+        let path = std::env::temp_dir().join(format!(
+            "pong-event-log-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let state = State::new(Arena::builtin(), false);
+        let mut log = EventLog::create(&path, state.game_state).unwrap();
+        log.log_tick(0, input, &[], &state).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|line| line["type"] == "input")
+            .map(|line| {
+                (
+                    String::from(line["action"].as_str().unwrap()),
+                    line["pressed"].as_bool().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn logs_a_tap_inside_one_tick() {
+        let mut input = Input::new();
+        input.set(Action::P1Up, true);
+        input.set(Action::P1Up, false);
+        assert_eq!(
+            logged_inputs("tap", &input),
+            [(String::from("P1Up"), true), (String::from("P1Up"), false)]
+        );
+    }
+
+    #[test]
+    fn logs_a_release_and_press_inside_one_tick() {
+        let mut input = Input::new();
+        input.set(Action::Confirm, true);
+        input.end_frame();
+        input.set(Action::Confirm, false);
+        input.set(Action::Confirm, true);
+        input.consume(Action::Confirm);
+        assert_eq!(
+            logged_inputs("repress", &input),
+            [
+                (String::from("Confirm"), false),
+                (String::from("Confirm"), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn held_actions_are_logged_once() {
+        let mut input = Input::new();
+        input.set(Action::P2Down, true);
+        assert_eq!(
+            logged_inputs("held", &input),
+            [(String::from("P2Down"), true)]
+        );
+        input.end_frame();
+        input.set(Action::P2Down, true);
+        assert!(logged_inputs("held", &input).is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::input::{Action, Input};
+
+use std::collections::VecDeque;
+
+// stick values closer to the centre than this are treated as resting
+const AXIS_DEADZONE: f32 = 0.2;
+
+pub type GamepadId = usize;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GamepadButton {
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    South,
+    East,
+    Start,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId, String),
+    Disconnected(GamepadId),
+    // vertical position of the left stick, up is positive
+    Axis(GamepadId, f32),
+    Button(GamepadId, GamepadButton, bool),
+}
+
+pub trait GamepadBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+}
+
+// used when no controller library is compiled in or it fails to start
+pub struct NullBackend;
+
+impl GamepadBackend for NullBackend {
+    fn poll(&mut self, _events: &mut Vec<GamepadEvent>) {}
+}
+
+// a virtual device that hands out whatever events were queued on it
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    pending: VecDeque<GamepadEvent>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, event: GamepadEvent) {
+        self.pending.push_back(event);
+    }
+}
+
+impl GamepadBackend for MockBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.extend(self.pending.drain(..));
+    }
+}
+
+#[cfg(feature = "gamepad")]
+pub struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+    // pads that were plugged in before we started never send a connected event
+    startup: Vec<GamepadEvent>,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsBackend {
+    pub fn new() -> Result<Self, gilrs::Error> {
+        let gilrs = gilrs::Gilrs::new()?;
+        let startup = gilrs
+            .gamepads()
+            .map(|(id, gamepad)| GamepadEvent::Connected(id.into(), String::from(gamepad.name())))
+            .collect();
+        Ok(Self { gilrs, startup })
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        use gilrs::{Axis, Button, EventType};
+
+        events.append(&mut self.startup);
+        while let Some(event) = self.gilrs.next_event() {
+            let id: GamepadId = event.id.into();
+            let button = |button, pressed| {
+                let button = match button {
+                    Button::DPadUp => GamepadButton::DPadUp,
+                    Button::DPadDown => GamepadButton::DPadDown,
+                    Button::DPadLeft => GamepadButton::DPadLeft,
+                    Button::DPadRight => GamepadButton::DPadRight,
+                    Button::South => GamepadButton::South,
+                    Button::East => GamepadButton::East,
+                    Button::Start => GamepadButton::Start,
+                    _ => return None,
+                };
+                Some(GamepadEvent::Button(id, button, pressed))
+            };
+            let translated = match event.event {
+                EventType::Connected => Some(GamepadEvent::Connected(
+                    id,
+                    String::from(self.gilrs.gamepad(event.id).name()),
+                )),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected(id)),
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                    Some(GamepadEvent::Axis(id, value))
+                }
+                EventType::ButtonPressed(pressed, _) => button(pressed, true),
+                EventType::ButtonReleased(released, _) => button(released, false),
+                _ => None,
+            };
+            events.extend(translated);
+        }
+    }
+}
+
+pub fn default_backend() -> Box<dyn GamepadBackend> {
+    #[cfg(feature = "gamepad")]
+    match GilrsBackend::new() {
+        Ok(backend) => return Box::new(backend),
+        Err(e) => eprintln!("gamepads unavailable: {}", e),
+    }
+    Box::new(NullBackend)
+}
+
+// feeds controller events into Input, pads only move the paddle of the player they're assigned to
+pub struct Gamepads {
+    backend: Box<dyn GamepadBackend>,
+    events: Vec<GamepadEvent>,
+    pub connected: Vec<(GamepadId, String)>,
+}
+
+impl Gamepads {
+    pub fn new(backend: Box<dyn GamepadBackend>) -> Self {
+        Self {
+            backend,
+            events: Vec::new(),
+            connected: Vec::new(),
+        }
+    }
+
+    // returns whether a pad was plugged in or removed
+    pub fn update(&mut self, input: &mut Input, assignments: &mut [Option<GamepadId>; 2]) -> bool {
+        self.events.clear();
+        self.backend.poll(&mut self.events);
+
+        let mut changed = false;
+        for event in self.events.drain(..) {
+            match event {
+                GamepadEvent::Connected(id, name) => {
+                    self.connected.retain(|(connected, _)| *connected != id);
+                    self.connected.push((id, name));
+                    changed = true;
+                }
+                GamepadEvent::Disconnected(id) => {
+                    self.connected.retain(|(connected, _)| *connected != id);
+                    for (player, assignment) in assignments.iter_mut().enumerate() {
+                        if *assignment == Some(id) {
+                            *assignment = None;
+                            release_player(input, player);
+                        }
+                    }
+                    changed = true;
+                }
+                GamepadEvent::Axis(id, value) => {
+                    let value = if value.abs() < AXIS_DEADZONE {
+                        0.0
+                    } else {
+                        value.clamp(-1.0, 1.0)
+                    };
+                    match player_for(assignments, id) {
+                        Some(0) => input.p1_axis = value,
+                        Some(_) => input.p2_axis = value,
+                        None => (),
+                    }
+                }
+                GamepadEvent::Button(id, button, pressed) => {
+                    let player = player_for(assignments, id);
+                    let action = match (button, player) {
+                        (GamepadButton::DPadUp, Some(0)) => Action::P1Up,
+                        (GamepadButton::DPadUp, Some(_)) => Action::P2Up,
+                        (GamepadButton::DPadUp, None) => Action::MenuUp,
+                        (GamepadButton::DPadDown, Some(0)) => Action::P1Down,
+                        (GamepadButton::DPadDown, Some(_)) => Action::P2Down,
+                        (GamepadButton::DPadDown, None) => Action::MenuDown,
+                        (GamepadButton::DPadLeft, _) => Action::Left,
+                        (GamepadButton::DPadRight, _) => Action::Right,
+                        (GamepadButton::South, _) => Action::Confirm,
+                        (GamepadButton::East, _) | (GamepadButton::Start, _) => Action::Back,
+                    };
+                    input.set(action, pressed);
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn player_for(assignments: &[Option<GamepadId>; 2], id: GamepadId) -> Option<usize> {
+    assignments
+        .iter()
+        .position(|assignment| *assignment == Some(id))
+}
+
+// a pad that goes away mid-match shouldn't leave its paddle moving
+fn release_player(input: &mut Input, player: usize) {
+    if player == 0 {
+        input.set(Action::P1Up, false);
+        input.set(Action::P1Down, false);
+        input.p1_axis = 0.0;
+    } else {
+        input.set(Action::P2Up, false);
+        input.set(Action::P2Down, false);
+        input.p2_axis = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pads(events: Vec<GamepadEvent>) -> Gamepads {
+        let mut backend = MockBackend::new();
+        for event in events {
+            backend.push(event);
+        }
+        Gamepads::new(Box::new(backend))
+    }
+
+    fn p1_axis_after(value: f32) -> f32 {
+        let mut input = Input::new();
+        let mut assignments = [Some(0), None];
+        pads(vec![GamepadEvent::Axis(0, value)]).update(&mut input, &mut assignments);
+        input.p1_axis
+    }
+
+    #[test]
+    fn connecting_lists_the_pad_once() {
+        let mut input = Input::new();
+        let mut assignments = [None, None];
+        let mut gamepads = pads(vec![
+            GamepadEvent::Connected(4, String::from("first")),
+            GamepadEvent::Connected(4, String::from("again")),
+        ]);
+
+        assert!(gamepads.update(&mut input, &mut assignments));
+        assert_eq!(gamepads.connected, vec![(4, String::from("again"))]);
+        assert!(!gamepads.update(&mut input, &mut assignments));
+    }
+
+    #[test]
+    fn disconnecting_releases_the_player() {
+        let mut input = Input::new();
+        let mut assignments = [Some(3), None];
+        let mut gamepads = pads(vec![
+            GamepadEvent::Connected(3, String::from("pad")),
+            GamepadEvent::Button(3, GamepadButton::DPadUp, true),
+            GamepadEvent::Axis(3, 0.8),
+            GamepadEvent::Disconnected(3),
+        ]);
+
+        assert!(gamepads.update(&mut input, &mut assignments));
+        assert!(gamepads.connected.is_empty());
+        assert_eq!(assignments, [None, None]);
+        assert!(!input.held(Action::P1Up));
+        assert_eq!(input.p1_axis, 0.0);
+    }
+
+    #[test]
+    fn axis_ignores_the_deadzone_and_clamps() {
+        assert_eq!(p1_axis_after(0.1), 0.0);
+        assert_eq!(p1_axis_after(-0.19), 0.0);
+        assert_eq!(p1_axis_after(-0.5), -0.5);
+        assert_eq!(p1_axis_after(1.5), 1.0);
+    }
+
+    #[test]
+    fn pads_drive_their_assigned_player() {
+        let mut input = Input::new();
+        let mut assignments = [Some(1), Some(2)];
+        let mut gamepads = pads(vec![
+            GamepadEvent::Button(2, GamepadButton::DPadUp, true),
+            GamepadEvent::Axis(2, -0.6),
+            GamepadEvent::Button(1, GamepadButton::DPadDown, true),
+            GamepadEvent::Button(5, GamepadButton::DPadUp, true),
+        ]);
+
+        assert!(!gamepads.update(&mut input, &mut assignments));
+        assert!(input.held(Action::P2Up));
+        assert!(!input.held(Action::P1Up));
+        assert_eq!(input.p2_axis, -0.6);
+        assert_eq!(input.p1_axis, 0.0);
+        assert!(input.held(Action::P1Down));
+        // an unassigned pad only steers the menus
+        assert!(input.held(Action::MenuUp));
+    }
+}
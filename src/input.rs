@@ -1,13 +1,123 @@
+#[cfg(feature = "window")]
 use dynamo_lib::keyboard::*;
+#[cfg(feature = "window")]
+use dynamo_lib::mouse::*;
+use serde::Serialize;
+
+// frames an action has to be held before it starts repeating, then frames between repeats
+const REPEAT_DELAY: u32 = 24;
+const REPEAT_INTERVAL: u32 = 6;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum Action {
+    P1Up,
+    P1Down,
+    P2Up,
+    P2Down,
+    // d-pad of a controller that isn't assigned to a player, only used by menus
+    MenuUp,
+    MenuDown,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Debug,
+    Click,
+    Screenshot,
+    Record,
+}
+
+const ACTION_COUNT: usize = Action::Record as usize + 1;
+
+impl Action {
+    pub const ALL: [Action; ACTION_COUNT] = [
+        Action::P1Up,
+        Action::P1Down,
+        Action::P2Up,
+        Action::P2Down,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Back,
+        Action::Debug,
+        Action::Click,
+        Action::Screenshot,
+        Action::Record,
+    ];
+}
+
+// what a player moves when holding up and down at the same time
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DirectionPolicy {
+    LastWins,
+    FirstWins,
+    Neutral,
+}
+
+impl DirectionPolicy {
+    pub const ALL: [DirectionPolicy; 3] = [
+        DirectionPolicy::LastWins,
+        DirectionPolicy::FirstWins,
+        DirectionPolicy::Neutral,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DirectionPolicy::LastWins => "Last wins",
+            DirectionPolicy::FirstWins => "First wins",
+            DirectionPolicy::Neutral => "Neutral",
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ActionState {
+    pub held: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+    held_frames: u32,
+    // when the current press started, compared between actions to tell which came first
+    pressed_order: u64,
+    // set once a system has handled the press so later systems in the same frame ignore it
+    consumed: bool,
+}
+
+impl ActionState {
+    fn set(&mut self, pressed: bool) {
+        // the os repeats pressed events while a key is held, only the first one is an edge
+        if pressed && !self.held {
+            self.just_pressed = true;
+            self.held_frames = 0;
+            self.consumed = false;
+        } else if !pressed && self.held {
+            self.just_released = true;
+        }
+        self.held = pressed;
+    }
+
+    fn repeated(&self) -> bool {
+        if self.consumed {
+            return false;
+        }
+        self.just_pressed
+            || (self.held
+                && self.held_frames >= REPEAT_DELAY
+                && (self.held_frames - REPEAT_DELAY).is_multiple_of(REPEAT_INTERVAL))
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct Input {
-    pub p1_up_pressed: bool,
-    pub p1_down_pressed: bool,
-    pub p2_up_pressed: bool,
-    pub p2_down_pressed: bool,
-    pub enter_pressed: bool,
-    pub esc_pressed: bool,
+    actions: [ActionState; ACTION_COUNT],
+    presses: u64,
+    // analog stick position from -1 to 1, scales paddle speed
+    pub p1_axis: f32,
+    pub p2_axis: f32,
+    // window position in pixels, None until the cursor first enters the window
+    pub mouse_position: Option<(f32, f32)>,
+    pub mouse_moved: bool,
 }
 
 impl Input {
@@ -15,45 +125,284 @@ impl Input {
         Default::default()
     }
 
+    #[cfg(feature = "window")]
     pub fn update(&mut self, input: KeyboardInput) {
         let pressed = input.state == KeyboardKeyState::Pressed;
-        match input.key {
-            KeyboardKey::Up => {
-                self.p2_up_pressed = pressed;
-            }
-            KeyboardKey::Down => {
-                self.p2_down_pressed = pressed;
-            }
-            KeyboardKey::W => {
-                self.p1_up_pressed = pressed;
-            }
-            KeyboardKey::S => {
-                self.p1_down_pressed = pressed;
-            }
-            KeyboardKey::Return => {
-                self.enter_pressed = pressed;
+        let action = match input.key {
+            KeyboardKey::Up => Action::P2Up,
+            KeyboardKey::Down => Action::P2Down,
+            KeyboardKey::W => Action::P1Up,
+            KeyboardKey::S => Action::P1Down,
+            KeyboardKey::Left | KeyboardKey::A => Action::Left,
+            KeyboardKey::Right | KeyboardKey::D => Action::Right,
+            KeyboardKey::Return => Action::Confirm,
+            KeyboardKey::Escape => Action::Back,
+            KeyboardKey::F3 => Action::Debug,
+            KeyboardKey::F10 => Action::Record,
+            KeyboardKey::F12 => Action::Screenshot,
+            _ => return,
+        };
+        self.set(action, pressed);
+    }
+
+    #[cfg(feature = "window")]
+    pub fn update_mouse(&mut self, input: MouseInput) {
+        match input {
+            MouseInput::Moved { position } => {
+                self.mouse_position = Some(position);
+                self.mouse_moved = true;
             }
-            KeyboardKey::Escape => {
-                self.esc_pressed = true;
+            MouseInput::Button {
+                button: MouseButton::Left,
+                state,
+            } => {
+                self.set(Action::Click, state == MouseButtonState::Pressed);
             }
             _ => (),
         }
     }
 
+    pub fn set(&mut self, action: Action, pressed: bool) {
+        let state = &mut self.actions[action as usize];
+        if pressed && !state.held {
+            self.presses += 1;
+            state.pressed_order = self.presses;
+        }
+        state.set(pressed);
+    }
+
+    // the raw edges of this frame, whether or not a system has consumed them
+    pub fn action_state(&self, action: Action) -> &ActionState {
+        &self.actions[action as usize]
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.actions[action as usize].held
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        let state = &self.actions[action as usize];
+        state.just_pressed && !state.consumed
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        let state = &self.actions[action as usize];
+        state.just_released && !state.consumed
+    }
+
+    // true on the press and then periodically while held, for stepping through menus
+    pub fn repeated(&self, action: Action) -> bool {
+        self.actions[action as usize].repeated()
+    }
+
+    // 1 for up, -1 for down, 0 for neither, with both held resolved by the policy
+    pub fn direction(&self, up: Action, down: Action, policy: DirectionPolicy) -> f32 {
+        match (self.held(up), self.held(down)) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            (false, false) => 0.0,
+            (true, true) => {
+                let up_last = self.actions[up as usize].pressed_order
+                    > self.actions[down as usize].pressed_order;
+                match policy {
+                    DirectionPolicy::LastWins if up_last => 1.0,
+                    DirectionPolicy::LastWins => -1.0,
+                    DirectionPolicy::FirstWins if up_last => -1.0,
+                    DirectionPolicy::FirstWins => 1.0,
+                    DirectionPolicy::Neutral => 0.0,
+                }
+            }
+        }
+    }
+
+    // whether anything was pressed or the mouse moved this frame
+    pub fn active(&self) -> bool {
+        self.mouse_moved || self.actions.iter().any(|state| state.just_pressed)
+    }
+
+    pub fn consume(&mut self, action: Action) {
+        self.actions[action as usize].consumed = true;
+    }
+
     pub fn ui_up_pressed(&self) -> bool {
-        self.p1_up_pressed || self.p2_up_pressed
+        self.repeated(Action::P1Up) || self.repeated(Action::P2Up) || self.repeated(Action::MenuUp)
     }
 
     pub fn ui_down_pressed(&self) -> bool {
-        self.p1_down_pressed || self.p2_down_pressed
+        self.repeated(Action::P1Down)
+            || self.repeated(Action::P2Down)
+            || self.repeated(Action::MenuDown)
+    }
+
+    pub fn ui_left_pressed(&self) -> bool {
+        self.repeated(Action::Left)
     }
 
+    pub fn ui_right_pressed(&self) -> bool {
+        self.repeated(Action::Right)
+    }
+
+    // marks every pending press as handled, held state is left alone
     pub fn clear(&mut self) {
-        self.p2_up_pressed = false;
-        self.p2_down_pressed = false;
-        self.p1_up_pressed = false;
-        self.p1_down_pressed = false;
-        self.enter_pressed = false;
-        self.esc_pressed = false;
+        for state in self.actions.iter_mut() {
+            state.consumed = true;
+        }
+        self.mouse_moved = false;
+    }
+
+    // edges only last for the frame they happened in
+    pub fn end_frame(&mut self) {
+        for state in self.actions.iter_mut() {
+            state.just_pressed = false;
+            state.just_released = false;
+            state.consumed = false;
+            if state.held {
+                state.held_frames += 1;
+            }
+        }
+        self.mouse_moved = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_actions_are_in_declaration_order() {
+        for (i, action) in Action::ALL.iter().enumerate() {
+            assert_eq!(*action as usize, i, "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn edges_last_one_frame() {
+        let mut input = Input::new();
+        input.set(Action::Confirm, true);
+        assert!(input.just_pressed(Action::Confirm));
+        assert!(input.held(Action::Confirm));
+
+        input.end_frame();
+        // the os repeating the key while it's held isn't another press
+        input.set(Action::Confirm, true);
+        assert!(!input.just_pressed(Action::Confirm));
+        assert!(input.held(Action::Confirm));
+
+        input.end_frame();
+        input.set(Action::Confirm, false);
+        assert!(input.just_released(Action::Confirm));
+        assert!(!input.held(Action::Confirm));
+
+        input.end_frame();
+        assert!(!input.just_released(Action::Confirm));
+    }
+
+    #[test]
+    fn back_only_fires_on_press() {
+        let mut input = Input::new();
+        input.set(Action::Back, true);
+        assert!(input.just_pressed(Action::Back));
+        input.end_frame();
+
+        input.set(Action::Back, false);
+        assert!(!input.just_pressed(Action::Back));
+        assert!(!input.repeated(Action::Back));
+    }
+
+    #[test]
+    fn consumed_presses_are_hidden_until_the_next_frame() {
+        let mut input = Input::new();
+        input.set(Action::Back, true);
+        input.consume(Action::Back);
+        assert!(!input.just_pressed(Action::Back));
+        assert!(input.action_state(Action::Back).just_pressed);
+
+        input.end_frame();
+        input.set(Action::Back, false);
+        input.set(Action::Back, true);
+        assert!(input.just_pressed(Action::Back));
+    }
+
+    // holds up, then down on the next frame, and returns where the policy points
+    fn both_held(policy: DirectionPolicy) -> (Input, f32) {
+        let mut input = Input::new();
+        input.set(Action::P1Up, true);
+        input.end_frame();
+        input.set(Action::P1Down, true);
+        let direction = input.direction(Action::P1Up, Action::P1Down, policy);
+        (input, direction)
+    }
+
+    #[test]
+    fn policies_resolve_both_directions_held() {
+        assert_eq!(both_held(DirectionPolicy::LastWins).1, -1.0);
+        assert_eq!(both_held(DirectionPolicy::FirstWins).1, 1.0);
+        assert_eq!(both_held(DirectionPolicy::Neutral).1, 0.0);
+    }
+
+    #[test]
+    fn releasing_one_direction_leaves_the_other() {
+        for policy in DirectionPolicy::ALL.iter().copied() {
+            let (mut input, _) = both_held(policy);
+            input.set(Action::P1Down, false);
+            assert_eq!(
+                input.direction(Action::P1Up, Action::P1Down, policy),
+                1.0,
+                "{:?}",
+                policy
+            );
+
+            let (mut input, _) = both_held(policy);
+            input.set(Action::P1Up, false);
+            assert_eq!(
+                input.direction(Action::P1Up, Action::P1Down, policy),
+                -1.0,
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn pressing_again_makes_a_direction_the_latest() {
+        let (mut input, _) = both_held(DirectionPolicy::LastWins);
+        input.end_frame();
+        input.set(Action::P1Up, false);
+        input.end_frame();
+        input.set(Action::P1Up, true);
+        let policy = DirectionPolicy::LastWins;
+        assert_eq!(input.direction(Action::P1Up, Action::P1Down, policy), 1.0);
+        let policy = DirectionPolicy::FirstWins;
+        assert_eq!(input.direction(Action::P1Up, Action::P1Down, policy), -1.0);
+    }
+
+    #[test]
+    fn held_menu_keys_repeat_after_a_delay() {
+        let mut input = Input::new();
+        input.set(Action::MenuDown, true);
+        let mut repeats = Vec::new();
+        for frame in 0..REPEAT_DELAY + 2 * REPEAT_INTERVAL + 1 {
+            if input.ui_down_pressed() {
+                repeats.push(frame);
+            }
+            input.end_frame();
+        }
+        assert_eq!(
+            repeats,
+            vec![
+                0,
+                REPEAT_DELAY,
+                REPEAT_DELAY + REPEAT_INTERVAL,
+                REPEAT_DELAY + 2 * REPEAT_INTERVAL
+            ]
+        );
+
+        // letting go stops it and a new press starts the delay over
+        input.set(Action::MenuDown, false);
+        input.end_frame();
+        input.set(Action::MenuDown, true);
+        assert!(input.ui_down_pressed());
+        input.end_frame();
+        assert!(!input.ui_down_pressed());
     }
 }
@@ -0,0 +1,23 @@
+// positions text relative to the window: the anchor is a fraction of the window size and the
+// offset is in pixels from that point
+#[derive(Debug, Copy, Clone)]
+pub struct Layout {
+    pub anchor: cgmath::Vector2<f32>,
+    pub offset: cgmath::Vector2<f32>,
+}
+
+impl Layout {
+    pub fn new(anchor: (f32, f32), offset: (f32, f32)) -> Self {
+        Self {
+            anchor: anchor.into(),
+            offset: offset.into(),
+        }
+    }
+
+    pub fn position(&self, window_size: (f32, f32)) -> cgmath::Vector2<f32> {
+        cgmath::Vector2 {
+            x: self.anchor.x * window_size.0 + self.offset.x,
+            y: self.anchor.y * window_size.1 + self.offset.y,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// the pong core as a library. State holds a whole game, Input is what the players are pressing,
+// the systems move one to the next and Simulation runs the ones a match needs for a tick. env,
+// batch and session drive all of that for training, bulk runs and the frontends
+
+pub mod ai;
+pub mod analytics;
+pub mod arena;
+mod attract;
+pub mod ball;
+pub mod batch;
+pub mod bot;
+pub mod capture;
+#[cfg(feature = "window")]
+mod debug;
+pub mod env;
+pub mod event;
+pub mod event_log;
+mod font;
+pub mod gamepad;
+pub mod input;
+mod layout;
+mod menu;
+pub mod player;
+#[cfg(feature = "window")]
+pub mod pong_game;
+pub mod raster;
+mod records;
+pub mod render;
+pub mod replay;
+pub mod save;
+pub mod session;
+pub mod simulation;
+pub mod state;
+mod stats;
+pub mod system;
+pub mod tournament;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod util;
+pub mod world;
+
+pub use ball::Ball;
+pub use env::{PaddleAction, PongEnv};
+pub use event::Event;
+pub use input::{Action, Input};
+pub use player::Player;
+pub use session::Session;
+pub use simulation::Simulation;
+pub use state::{GameState, State};
+pub use system::System;
@@ -1,15 +1,319 @@
+#[cfg(feature = "window")]
 use dynamo_lib::start;
 
-mod ball;
-mod input;
-mod player;
-mod pong_game;
-mod state;
-mod system;
-mod util;
-use pong_game::PongGame;
+#[cfg(feature = "window")]
+use pong::bot::{self, Bot};
+use pong::capture;
+use pong::env::PongEnv;
+use pong::event_log::EventLog;
+#[cfg(feature = "window")]
+use pong::pong_game::PongGame;
+use pong::replay::{self, Replay, ReplayHeader};
+use pong::state::{GameMode, GameState, MAX_POINTS_TO_WIN};
+use pong::tournament::{self, Entrant, TournamentConfig};
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "usage: pong [--mode MODE] [--points-to-win N] [--p1 PLAYER] [--p2 PLAYER]
+            [--seed N] [--replay FILE [--capture FILE] | --record FILE] [--event-log FILE]
+            [--headless] [--config FILE]
+
+  --mode MODE         the game mode, versus
+  --points-to-win N   points needed to win a match, 1 to 21
+  --p1, --p2 PLAYER   human, ai:easy, ai:normal, ai:hard or bot:COMMAND, humans by default
+  --seed N            start every match from this seed, random otherwise
+  --replay FILE       watch a recorded match
+  --capture FILE      draw the replay without a window and save it as a .gif, or its last
+                      frame as a .png
+  --record FILE       record each match to FILE, the last one played is kept
+  --event-log FILE    write every hit, score, state change and input to FILE as json lines
+  --headless          play one match without a window and print the result,
+                      both players must be ai or bot. with --replay prints the final score.
+                      the only way to play when built without the window feature
+  --config FILE       read any of the options above from a json file, such as
+                      {\"points_to_win\": 11, \"p2\": \"ai:hard\"}, the command line wins
+
+for example
+  pong --p2 ai:hard --points-to-win 11
+  pong --headless --p1 ai:hard --p2 \"bot:python3 'my bot.py'\" --seed 7 --record match.jsonl
+  pong --replay match.jsonl --capture match.gif";
+
+// everything the command line and the config file can set, the config file's keys are the
+// long option names with underscores
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Options {
+    mode: Option<String>,
+    points_to_win: Option<u32>,
+    p1: Option<String>,
+    p2: Option<String>,
+    seed: Option<u64>,
+    replay: Option<PathBuf>,
+    record: Option<PathBuf>,
+    capture: Option<PathBuf>,
+    event_log: Option<PathBuf>,
+    headless: bool,
+}
+
+impl Options {
+    // keeps what's set here and fills the rest in from other
+    fn or(self, other: Options) -> Options {
+        Options {
+            mode: self.mode.or(other.mode),
+            points_to_win: self.points_to_win.or(other.points_to_win),
+            p1: self.p1.or(other.p1),
+            p2: self.p2.or(other.p2),
+            seed: self.seed.or(other.seed),
+            replay: self.replay.or(other.replay),
+            record: self.record.or(other.record),
+            capture: self.capture.or(other.capture),
+            event_log: self.event_log.or(other.event_log),
+            headless: self.headless || other.headless,
+        }
+    }
+}
+
+// None is a person at the keyboard
+type PlayerSpec = Option<Entrant>;
 
 fn main() {
-    let pong_game = PongGame::new();
+    let options = parse_args();
+    let mode = match &options.mode {
+        Some(name) => GameMode::from_name(name).unwrap_or_else(|| {
+            let modes: Vec<&str> = GameMode::ALL.iter().map(|mode| mode.name()).collect();
+            fail(&format!(
+                "unknown mode {}, the modes are {}",
+                name,
+                modes.join(", ").to_lowercase()
+            ))
+        }),
+        None => GameMode::Versus,
+    };
+    if let Some(points) = options.points_to_win {
+        if !(1..=MAX_POINTS_TO_WIN).contains(&points) {
+            fail(&format!(
+                "--points-to-win must be between 1 and {}, not {}",
+                MAX_POINTS_TO_WIN, points
+            ));
+        }
+    }
+    let players = [
+        player(options.p1.as_deref(), "--p1"),
+        player(options.p2.as_deref(), "--p2"),
+    ];
+    if options.replay.is_some() && options.record.is_some() {
+        fail("--replay and --record can't be used together");
+    }
+    if options.capture.is_some() && options.replay.is_none() {
+        fail("--capture needs a --replay to draw");
+    }
+    let replay = options.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| fail(&format!("{}: {}", path.display(), e)))
+    });
+
+    if let (Some(path), Some(replay)) = (&options.capture, &replay) {
+        if let Err(e) = capture::capture_replay(replay, path) {
+            fail(&format!("{}: {}", path.display(), e));
+        }
+        println!("saved {}", path.display());
+        return;
+    }
+
+    if options.headless {
+        match replay {
+            Some(replay) => play_back(&options, &replay),
+            None => play_headless(&options, players),
+        }
+        return;
+    }
+
+    run_window(&options, mode, players, replay);
+}
+
+#[cfg(feature = "window")]
+fn run_window(options: &Options, mode: GameMode, players: [PlayerSpec; 2], replay: Option<Replay>) {
+    let mut pong_game = PongGame::new();
+    let session = &mut pong_game.session;
+    session.set_mode(mode);
+    if let Some(points) = options.points_to_win {
+        session.set_points_to_win(points);
+    }
+    if let Some(seed) = options.seed {
+        session.set_seed(seed);
+    }
+    if let Some(path) = &options.record {
+        session.record_to(path);
+    }
+    if let Some(path) = &options.event_log {
+        if let Err(e) = session.log_events_to(path) {
+            fail(&format!("could not write {}: {}", path.display(), e));
+        }
+    }
+    if let Some(replay) = replay {
+        session.set_replay(replay);
+    }
+    for (side, spec) in players.iter().enumerate() {
+        match spec {
+            Some(Entrant::Ai(difficulty)) => session.set_ai(side, *difficulty),
+            Some(Entrant::Bot(command)) => session.set_bot(side, bot(command, side)),
+            None => (),
+        }
+    }
     start("Pong", Box::new(pong_game));
 }
+
+#[cfg(not(feature = "window"))]
+fn run_window(_: &Options, _: GameMode, _: [PlayerSpec; 2], _: Option<Replay>) {
+    fail("pong was built without the window feature, only --headless can be played");
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut config = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--mode" => options.mode = Some(value(&mut args, &arg)),
+            "--points-to-win" => options.points_to_win = Some(number(&mut args, &arg)),
+            "--p1" => options.p1 = Some(value(&mut args, &arg)),
+            "--p2" => options.p2 = Some(value(&mut args, &arg)),
+            "--seed" => options.seed = Some(number(&mut args, &arg)),
+            "--replay" => options.replay = Some(PathBuf::from(value(&mut args, &arg))),
+            "--record" => options.record = Some(PathBuf::from(value(&mut args, &arg))),
+            "--capture" => options.capture = Some(PathBuf::from(value(&mut args, &arg))),
+            "--event-log" => options.event_log = Some(PathBuf::from(value(&mut args, &arg))),
+            "--headless" => options.headless = true,
+            "--config" => config = Some(PathBuf::from(value(&mut args, &arg))),
+            _ => fail(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    match config {
+        Some(path) => options.or(load_config(&path)),
+        None => options,
+    }
+}
+
+fn load_config(path: &Path) -> Options {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(&format!("could not read {}: {}", path.display(), e)));
+    serde_json::from_str(&contents)
+        .unwrap_or_else(|e| fail(&format!("invalid config {}: {}", path.display(), e)))
+}
+
+fn player(spec: Option<&str>, flag: &str) -> PlayerSpec {
+    match spec {
+        None | Some("human") => None,
+        Some(spec) => Some(Entrant::parse(spec).unwrap_or_else(|| {
+            fail(&format!(
+                "invalid player {} for {}, use human, ai:easy, ai:normal, ai:hard or bot:COMMAND",
+                spec, flag
+            ))
+        })),
+    }
+}
+
+#[cfg(feature = "window")]
+fn bot(command: &str, side: usize) -> Bot {
+    Bot::from_command(command, bot::DEFAULT_TIMEOUT)
+        .unwrap_or_else(|e| fail(&format!("--p{}: {}", side + 1, e)))
+}
+
+fn play_back(options: &Options, replay: &Replay) {
+    let mut event_log = open_event_log(options);
+    let mut tick = 0;
+    let ((p1, p2), ticks) = replay.play_back(|env| {
+        log_tick(&mut event_log, tick, env);
+        tick += 1;
+    });
+    println!("{} - {} after {} ticks", p1, p2, ticks);
+}
+
+fn play_headless(options: &Options, players: [PlayerSpec; 2]) {
+    let entrants = match &players {
+        [Some(p1), Some(p2)] => [p1, p2],
+        _ => fail(
+            "--headless needs both players to be ai or bot, there's nobody to use the keyboard",
+        ),
+    };
+    let mut config = TournamentConfig {
+        seed: options.seed.unwrap_or_else(rand::random),
+        ..Default::default()
+    };
+    if let Some(points) = options.points_to_win {
+        config.points_to_win = points;
+    }
+
+    let mut event_log = open_event_log(options);
+    let mut replay = None;
+    let mut tick = 0;
+    let played = tournament::play(entrants, &config, |env| {
+        log_tick(&mut event_log, tick, env);
+        tick += 1;
+        let state = env.state();
+        if options.record.is_some() {
+            replay
+                .get_or_insert_with(|| Replay::new(ReplayHeader::new(state, config.seed)))
+                .frames
+                .push(replay::frame(state));
+        }
+    });
+    let result = played.unwrap_or_else(|e| fail(&e.to_string()));
+    if let (Some(path), Some(replay)) = (&options.record, replay) {
+        if let Err(e) = replay.save(path) {
+            eprintln!("{}: {}", path.display(), e);
+        }
+    }
+
+    println!(
+        "{} {} - {} {}",
+        result.players[0], result.score[0], result.score[1], result.players[1]
+    );
+    match (result.winner, &result.note) {
+        (_, Some(note)) => println!("{}", note),
+        (Some(side), None) => println!("{} wins", result.players[side]),
+        (None, None) => println!("drawn"),
+    }
+}
+
+// opened before the match so a path that can't be written fails straight away
+fn open_event_log(options: &Options) -> Option<EventLog> {
+    options.event_log.as_ref().map(|path| {
+        // a headless match starts on the serve
+        EventLog::create(path, GameState::Serving)
+            .unwrap_or_else(|e| fail(&format!("could not write {}: {}", path.display(), e)))
+    })
+}
+
+fn log_tick(event_log: &mut Option<EventLog>, tick: u64, env: &PongEnv) {
+    if let Some(log) = event_log {
+        if let Err(e) = log.log_tick(tick, env.input(), env.events(), env.state()) {
+            eprintln!("stopped logging events, {}", e);
+            *event_log = None;
+        }
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} needs a value", flag)))
+}
+
+fn number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> T {
+    let value = value(args, flag);
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("{} needs a whole number, not {}", flag, value)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
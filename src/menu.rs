@@ -0,0 +1,439 @@
+use crate::event::Event;
+use crate::input::{Action, Input};
+use crate::layout::Layout;
+use crate::render::RenderText;
+use crate::state::PongText;
+
+use std::fmt::Write;
+
+const ITEM_SPACING: f32 = 60.0;
+const ITEM_SIZE: f32 = 32.0;
+// the text renderer can't measure strings, so hit boxes assume an average glyph width
+const GLYPH_WIDTH: f32 = ITEM_SIZE * 0.6;
+const ENABLED_COLOR: (f32, f32, f32, f32) = (1.0, 1.0, 1.0, 1.0);
+const DISABLED_COLOR: (f32, f32, f32, f32) = (0.4, 0.4, 0.4, 1.0);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MenuAction {
+    Continue,
+    Play,
+    PlayAgain,
+    Resume,
+    Arena,
+    Settings,
+    Records,
+    MainMenu,
+    Back,
+    Quit,
+    PointsToWin,
+    Sound,
+    Assist,
+    Player1Mouse,
+    Player2Mouse,
+    Player1Direction,
+    Player2Direction,
+    Player1Gamepad,
+    Player2Gamepad,
+}
+
+#[derive(Debug, Clone)]
+pub enum MenuItemKind {
+    Button,
+    Toggle(bool),
+    Slider {
+        value: i32,
+        min: i32,
+        max: i32,
+    },
+    Choice {
+        options: Vec<String>,
+        selected: usize,
+    },
+}
+
+pub struct MenuItem {
+    pub label: String,
+    pub action: MenuAction,
+    pub kind: MenuItemKind,
+    pub enabled: bool,
+    pub text: PongText,
+}
+
+impl MenuItem {
+    pub fn new(label: &str, action: MenuAction, kind: MenuItemKind) -> Self {
+        Self {
+            label: String::from(label),
+            action,
+            kind,
+            enabled: true,
+            text: PongText {
+                visible: false,
+                layout: Layout::new((0.0, 0.0), (0.0, 0.0)),
+                render_text: RenderText {
+                    color: ENABLED_COLOR.into(),
+                    size: ITEM_SIZE,
+                    ..Default::default()
+                },
+            },
+        }
+    }
+
+    pub fn button(label: &str, action: MenuAction) -> Self {
+        Self::new(label, action, MenuItemKind::Button)
+    }
+
+    pub fn toggle(label: &str, action: MenuAction, on: bool) -> Self {
+        Self::new(label, action, MenuItemKind::Toggle(on))
+    }
+
+    pub fn slider(label: &str, action: MenuAction, value: i32, min: i32, max: i32) -> Self {
+        Self::new(label, action, MenuItemKind::Slider { value, min, max })
+    }
+
+    pub fn choice(label: &str, action: MenuAction, options: Vec<String>, selected: usize) -> Self {
+        Self::new(label, action, MenuItemKind::Choice { options, selected })
+    }
+
+    // writes over text rather than building a new string, menus are refreshed every frame
+    fn write_display_text(&self, text: &mut String) {
+        text.clear();
+        // writing to a string can't fail
+        let _ = match &self.kind {
+            MenuItemKind::Button => write!(text, "{}", self.label),
+            MenuItemKind::Toggle(on) => {
+                write!(text, "{}: {}", self.label, if *on { "On" } else { "Off" })
+            }
+            MenuItemKind::Slider { value, .. } => write!(text, "{}: < {} >", self.label, value),
+            MenuItemKind::Choice { options, selected } => {
+                let option = options.get(*selected).map_or("", String::as_str);
+                write!(text, "{}: < {} >", self.label, option)
+            }
+        };
+    }
+
+    fn width(&self) -> f32 {
+        self.text.render_text.text.chars().count() as f32 * GLYPH_WIDTH
+    }
+
+    fn contains(&self, point: (f32, f32)) -> bool {
+        let position = self.text.render_text.position;
+        point.0 >= position.x
+            && point.0 <= position.x + self.width()
+            && point.1 >= position.y
+            && point.1 <= position.y + ITEM_SIZE
+    }
+
+    // left and right step sliders and choices, returns whether the value changed
+    fn adjust(&mut self, step: i32) -> bool {
+        match &mut self.kind {
+            MenuItemKind::Button => false,
+            MenuItemKind::Toggle(on) => {
+                *on = !*on;
+                true
+            }
+            MenuItemKind::Slider { value, min, max } => {
+                let next = (*value + step).max(*min).min(*max);
+                let changed = next != *value;
+                *value = next;
+                changed
+            }
+            MenuItemKind::Choice { options, selected } => {
+                if options.is_empty() {
+                    return false;
+                }
+                let count = options.len() as i32;
+                *selected = (*selected as i32 + step).rem_euclid(count) as usize;
+                count > 1
+            }
+        }
+    }
+}
+
+pub struct Menu {
+    pub items: Vec<MenuItem>,
+    pub focus: usize,
+    pub layout: Layout,
+    pub visible: bool,
+}
+
+impl Menu {
+    pub fn new(layout: Layout, items: Vec<MenuItem>) -> Self {
+        let mut menu = Self {
+            items,
+            focus: 0,
+            layout,
+            visible: false,
+        };
+        menu.reset_focus();
+        menu
+    }
+
+    pub fn item(&self, action: MenuAction) -> Option<&MenuItem> {
+        self.items.iter().find(|item| item.action == action)
+    }
+
+    pub fn item_mut(&mut self, action: MenuAction) -> Option<&mut MenuItem> {
+        self.items.iter_mut().find(|item| item.action == action)
+    }
+
+    pub fn set_enabled(&mut self, action: MenuAction, enabled: bool) {
+        if let Some(item) = self.item_mut(action) {
+            item.enabled = enabled;
+        }
+        if self.items.get(self.focus).is_some_and(|item| !item.enabled) {
+            self.reset_focus();
+        }
+        self.refresh();
+    }
+
+    pub fn toggle_value(&self, action: MenuAction) -> Option<bool> {
+        match self.item(action).map(|item| &item.kind) {
+            Some(MenuItemKind::Toggle(on)) => Some(*on),
+            _ => None,
+        }
+    }
+
+    pub fn slider_value(&self, action: MenuAction) -> Option<i32> {
+        match self.item(action).map(|item| &item.kind) {
+            Some(MenuItemKind::Slider { value, .. }) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn set_slider(&mut self, action: MenuAction, new_value: i32) {
+        if let Some(MenuItemKind::Slider { value, min, max }) =
+            self.item_mut(action).map(|item| &mut item.kind)
+        {
+            *value = new_value.max(*min).min(*max);
+        }
+        self.refresh();
+    }
+
+    pub fn selected(&self, action: MenuAction) -> Option<usize> {
+        match self.item(action).map(|item| &item.kind) {
+            Some(MenuItemKind::Choice { selected, .. }) => Some(*selected),
+            _ => None,
+        }
+    }
+
+    pub fn set_selected(&mut self, action: MenuAction, index: usize) {
+        if let Some(MenuItemKind::Choice { options, selected }) =
+            self.item_mut(action).map(|item| &mut item.kind)
+        {
+            *selected = index.min(options.len().saturating_sub(1));
+        }
+        self.refresh();
+    }
+
+    pub fn set_options(&mut self, action: MenuAction, new_options: Vec<String>, index: usize) {
+        if let Some(MenuItemKind::Choice { options, selected }) =
+            self.item_mut(action).map(|item| &mut item.kind)
+        {
+            *selected = index.min(new_options.len().saturating_sub(1));
+            *options = new_options;
+        }
+        self.refresh();
+    }
+
+    pub fn reset_focus(&mut self) {
+        self.focus = self.items.iter().position(|item| item.enabled).unwrap_or(0);
+        self.refresh();
+    }
+
+    // moves focus by one enabled item, wrapping around at either end
+    fn move_focus(&mut self, forward: bool) -> bool {
+        let count = self.items.len();
+        for offset in 1..count {
+            let index = if forward {
+                (self.focus + offset) % count
+            } else {
+                (self.focus + count - offset) % count
+            };
+            if self.items[index].enabled {
+                self.focus = index;
+                return true;
+            }
+        }
+        false
+    }
+
+    // handles navigation and returns the action of an item that was activated or changed
+    pub fn update(&mut self, input: &mut Input, events: &mut Vec<Event>) -> Option<MenuAction> {
+        if self.items.is_empty() {
+            return None;
+        }
+
+        let action = self.handle_input(input, events);
+        self.refresh();
+        action
+    }
+
+    fn handle_input(&mut self, input: &mut Input, events: &mut Vec<Event>) -> Option<MenuAction> {
+        if let Some(action) = self.handle_mouse(input, events) {
+            return Some(action);
+        }
+
+        if input.ui_down_pressed() || input.ui_up_pressed() {
+            if self.move_focus(input.ui_down_pressed()) {
+                events.push(Event::FocusChanged);
+            }
+            input.clear();
+        }
+
+        let item = &mut self.items[self.focus];
+        if !item.enabled {
+            return None;
+        }
+
+        if input.ui_left_pressed() || input.ui_right_pressed() {
+            let step = if input.ui_right_pressed() { 1 } else { -1 };
+            input.clear();
+            if item.adjust(step) {
+                events.push(Event::FocusChanged);
+                return Some(item.action);
+            }
+        }
+
+        if input.just_pressed(Action::Confirm) {
+            input.consume(Action::Confirm);
+            if let MenuItemKind::Slider { .. } = item.kind {
+                return None;
+            }
+            if let MenuItemKind::Toggle(_) | MenuItemKind::Choice { .. } = item.kind {
+                item.adjust(1);
+            }
+            events.push(Event::ButtonPressed);
+            return Some(item.action);
+        }
+
+        None
+    }
+
+    // hovering focuses an item and clicking activates it, sliders step towards the clicked side
+    fn handle_mouse(&mut self, input: &mut Input, events: &mut Vec<Event>) -> Option<MenuAction> {
+        let position = input.mouse_position?;
+        let hovered = self
+            .items
+            .iter()
+            .position(|item| item.enabled && item.contains(position))?;
+
+        if input.mouse_moved && hovered != self.focus {
+            self.focus = hovered;
+            events.push(Event::FocusChanged);
+        }
+
+        // like most ui buttons a click lands when the button comes back up
+        if !input.just_released(Action::Click) {
+            return None;
+        }
+        input.consume(Action::Click);
+        self.focus = hovered;
+
+        let item = &mut self.items[hovered];
+        let changed = match item.kind {
+            MenuItemKind::Button => true,
+            MenuItemKind::Slider { .. } => {
+                let middle = item.text.render_text.position.x + item.width() * 0.5;
+                item.adjust(if position.0 < middle { -1 } else { 1 })
+            }
+            _ => item.adjust(1),
+        };
+        if changed {
+            events.push(Event::ButtonPressed);
+            return Some(item.action);
+        }
+        None
+    }
+
+    pub fn layout(&mut self, window_size: (f32, f32)) {
+        let origin = self.layout.position(window_size);
+        for (i, item) in self.items.iter_mut().enumerate() {
+            item.text.render_text.position = (origin.x, origin.y + i as f32 * ITEM_SPACING).into();
+        }
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item = &PongText> {
+        let count = if self.visible { self.items.len() } else { 0 };
+        self.items.iter().take(count).map(|item| &item.text)
+    }
+
+    // keeps the item texts in sync with their values and the focus
+    fn refresh(&mut self) {
+        let focus = self.focus;
+        for (i, item) in self.items.iter_mut().enumerate() {
+            let mut text = std::mem::take(&mut item.text.render_text.text);
+            item.write_display_text(&mut text);
+            item.text.render_text.text = text;
+            item.text.set_focus(i == focus);
+            item.text.render_text.color = if item.enabled {
+                ENABLED_COLOR.into()
+            } else {
+                DISABLED_COLOR.into()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display_text(item: &MenuItem) -> String {
+        let mut text = String::new();
+        item.write_display_text(&mut text);
+        text
+    }
+
+    fn menu(items: Vec<MenuItem>) -> Menu {
+        Menu::new(Layout::new((0.0, 0.0), (0.0, 0.0)), items)
+    }
+
+    #[test]
+    fn empty_menu_does_nothing() {
+        let mut menu = menu(Vec::new());
+        menu.set_enabled(MenuAction::Play, false);
+        menu.reset_focus();
+        assert_eq!(menu.focus, 0);
+
+        let mut input = Input::new();
+        input.set(Action::Confirm, true);
+        assert_eq!(menu.update(&mut input, &mut Vec::new()), None);
+    }
+
+    #[test]
+    fn disabling_the_focused_item_moves_focus() {
+        let mut menu = menu(vec![
+            MenuItem::button("Play", MenuAction::Play),
+            MenuItem::button("Quit", MenuAction::Quit),
+        ]);
+        menu.set_enabled(MenuAction::Play, false);
+        assert_eq!(menu.focus, 1);
+        assert_eq!(menu.items[0].text.render_text.color, DISABLED_COLOR.into());
+    }
+
+    #[test]
+    fn choice_without_options() {
+        let mut item = MenuItem::choice("Arena", MenuAction::Arena, Vec::new(), 0);
+        assert_eq!(display_text(&item), "Arena: <  >");
+        assert!(!item.adjust(1));
+        assert!(!item.adjust(-1));
+
+        let mut menu = menu(vec![item]);
+        menu.set_options(MenuAction::Arena, Vec::new(), 3);
+        assert_eq!(menu.selected(MenuAction::Arena), Some(0));
+        let mut input = Input::new();
+        input.set(Action::Confirm, true);
+        menu.update(&mut input, &mut Vec::new());
+        assert_eq!(menu.items[0].text.render_text.text, "Arena: <  >");
+    }
+
+    #[test]
+    fn choice_wraps_around() {
+        let options = vec![String::from("Classic"), String::from("Pillars")];
+        let mut item = MenuItem::choice("Arena", MenuAction::Arena, options, 0);
+        assert!(item.adjust(-1));
+        assert_eq!(display_text(&item), "Arena: < Pillars >");
+        assert!(item.adjust(1));
+        assert_eq!(display_text(&item), "Arena: < Classic >");
+    }
+}
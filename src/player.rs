@@ -1,9 +1,13 @@
 use crate::ball::Ball;
-use dynamo_lib::geometry::quad::Quad;
+use crate::render::Quad;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub quad: Quad,
     pub score: u32,
+    // set every frame by the visibility system, so it isn't saved
+    #[serde(skip)]
     pub visible: bool,
 }
 
@@ -33,17 +37,14 @@ impl Player {
         self.quad = Quad::new(position, self.quad.size);
     }
 
-    pub fn contains(&self, ball: &Ball) -> bool {
+    pub fn bounds(&self) -> (cgmath::Vector2<f32>, cgmath::Vector2<f32>) {
         let radii = self.size() * 0.5;
-        let min = self.position() - radii;
-        let max = self.position() + radii;
-
-        let b_radii = cgmath::Vector2 {
-            x: ball.radius(),
-            y: ball.radius(),
-        };
-        let b_min = ball.position() - b_radii;
-        let b_max = ball.position() + b_radii;
+        (self.position() - radii, self.position() + radii)
+    }
+
+    pub fn contains(&self, ball: &Ball) -> bool {
+        let (min, max) = self.bounds();
+        let (b_min, b_max) = ball.bounds();
 
         min.x < b_max.x && max.x > b_min.x && min.y < b_max.y && max.y > b_min.y
     }
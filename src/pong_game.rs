@@ -1,14 +1,19 @@
 use dynamo_lib::geometry::Geometry;
 use dynamo_lib::keyboard::*;
+use dynamo_lib::mouse::MouseInput;
 use dynamo_lib::renderer::render_text::TextRenderer;
 use dynamo_lib::sound::SoundSystem;
 use dynamo_lib::Game;
 
 use std::io::Cursor;
 
-use crate::input::Input;
-use crate::state::*;
-use crate::system::*;
+use crate::arena::Arena;
+use crate::debug::DebugOverlay;
+use crate::event::Event;
+use crate::input::Action;
+use crate::render::DynamoSink;
+use crate::save::SavedMatch;
+use crate::session::Session;
 
 const BOUNCE_BYTES: &[u8] = include_bytes!("../res/sounds/4362__noisecollector__pongblipa-4.wav");
 
@@ -28,44 +33,44 @@ impl SoundPack {
   }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum Event {
-  ButtonPressed,
-  FocusChanged,
-  BallBounce(cgmath::Vector2<f32>),
-  Score(u32),
+impl Default for SoundPack {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 pub struct PongGame {
-  pub input: Input,
-  events: Vec<Event>,
-  state: State,
-  menu_system: MenuSystem,
-  serving_system: ServingSystem,
-  play_system: PlaySystem,
-  pause_system: PauseSystem,
-  ball_system: BallSystem,
-  game_over_system: GameOverSystem,
-  visibility_system: VisibilitySystem,
+  pub session: Session,
   sound_pack: SoundPack,
+  debug_overlay: DebugOverlay,
 }
 
 impl PongGame {
   pub fn new() -> Self {
     Self {
-      input: Input::new(),
-      events: Vec::new(),
-      state: State::new(),
-      menu_system: MenuSystem,
-      serving_system: ServingSystem::new(),
-      play_system: PlaySystem,
-      pause_system: PauseSystem,
-      ball_system: BallSystem,
-      game_over_system: GameOverSystem::new(),
-      visibility_system: VisibilitySystem,
+      session: Session::new(Arena::load_all(), SavedMatch::exists()),
       sound_pack: SoundPack::new(),
+      debug_overlay: DebugOverlay::new(),
     }
   }
+
+  fn draw(&mut self, geometry: &mut Geometry, text_renderer: &mut TextRenderer) {
+    geometry.reset();
+    text_renderer.reset();
+
+    let mut sink = DynamoSink {
+      geometry,
+      text_renderer,
+    };
+    self.session.draw(&mut sink);
+    self.debug_overlay.draw(self.session.state(), &mut sink);
+  }
+}
+
+impl Default for PongGame {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Game for PongGame {
@@ -74,10 +79,10 @@ impl Game for PongGame {
     geometry: &mut Geometry,
     text_renderer: &mut TextRenderer,
     _sound_system: &SoundSystem,
-    _window_size: (f32, f32),
+    window_size: (f32, f32),
   ) {
-    self.menu_system.start(&mut self.state);
-    self.state.initialize(geometry, text_renderer);
+    self.session.start(window_size);
+    self.draw(geometry, text_renderer);
   }
 
   fn update(
@@ -86,91 +91,54 @@ impl Game for PongGame {
     text_renderer: &mut TextRenderer,
     sound_system: &SoundSystem,
   ) {
-    for event in &self.events {
-      match event {
-        Event::FocusChanged | Event::ButtonPressed => {
-          sound_system.queue(self.sound_pack.bounce());
-        }
-        Event::BallBounce(_pos) => {
-          sound_system.queue(self.sound_pack.bounce());
-        }
-        Event::Score(_) => {
-          sound_system.queue(self.sound_pack.bounce());
-        }
-      }
+    self.debug_overlay.begin_tick();
+    if self.session.input.just_pressed(Action::Debug) {
+      self.debug_overlay.toggle();
+      self.session.input.consume(Action::Debug);
     }
-    self.events.clear();
-
-    self
-      .visibility_system
-      .update_state(&mut self.input, &mut self.state, &mut self.events);
-    match self.state.game_state {
-      GameState::MainMenu => {
-        self
-          .menu_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        if self.state.game_state == GameState::Serving {
-          self.serving_system.start(&mut self.state);
+    self.session.update();
+    self.debug_overlay.end_tick();
+
+    if self.session.state().settings.sound {
+      for event in self.session.events() {
+        match event {
+          Event::FocusChanged | Event::ButtonPressed => {
+            sound_system.queue(self.sound_pack.bounce());
+          }
+          Event::BallBounce(_pos) => {
+            sound_system.queue(self.sound_pack.bounce());
+          }
+          Event::PaddleHit { .. } => {
+            sound_system.queue(self.sound_pack.bounce());
+          }
+          Event::Score(_) => {
+            sound_system.queue(self.sound_pack.bounce());
+          }
+          Event::Serve(_) => {}
         }
       }
-      GameState::Serving => {
-        self
-          .serving_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        self
-          .play_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        if self.state.game_state == GameState::Playing {
-          self.play_system.start(&mut self.state);
-        }
-      }
-      GameState::Playing => {
-        self
-          .ball_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        self
-          .play_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        if self.state.game_state == GameState::Serving {
-          self.serving_system.start(&mut self.state);
-        } else if self.state.game_state == GameState::GameOver {
-          self.game_over_system.start(&mut self.state);
-        }
-      }
-      GameState::Paused => {
-        self
-          .pause_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-      }
-      GameState::GameOver => {
-        self
-          .game_over_system
-          .update_state(&mut self.input, &mut self.state, &mut self.events);
-        if self.state.game_state == GameState::MainMenu {
-          self.menu_system.start(&mut self.state);
-        }
-      }
-      GameState::Quitting => {}
     }
 
-    geometry.reset();
-    text_renderer.reset();
+    self.draw(geometry, text_renderer);
+  }
 
-    self.state.update(geometry, text_renderer);
+  fn resize(&mut self, window_size: (f32, f32)) {
+    self.session.resize(window_size);
   }
 
   fn process_keyboard(&mut self, input: KeyboardInput) {
-    self.input.update(input);
+    self.session.input.update(input);
+  }
+
+  fn process_mouse(&mut self, input: MouseInput) {
+    self.session.input.update_mouse(input);
   }
 
   fn is_quitting(&self) -> bool {
-    self.state.game_state == GameState::Quitting
+    self.session.is_quitting()
   }
 
   fn focus_changed(&mut self, focus: bool) {
-    if !focus {
-      self.pause_system.start(&mut self.state);
-      self.state.pause_game();
-    }
+    self.session.focus_changed(focus);
   }
 }
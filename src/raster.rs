@@ -0,0 +1,343 @@
+use crate::font;
+use crate::render::{Quad, RenderSink, RenderText};
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+    // gif frames are at most 65535 pixels on a side
+    TooLarge(u32, u32),
+    // only png and gif files can be written
+    Format(PathBuf),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "could not write image: {}", e),
+            ExportError::Png(e) => write!(f, "could not encode png: {}", e),
+            ExportError::Gif(e) => write!(f, "could not encode gif: {}", e),
+            ExportError::TooLarge(width, height) => {
+                write!(f, "{}x{} is too large for a gif", width, height)
+            }
+            ExportError::Format(path) => {
+                write!(
+                    f,
+                    "can only save .png or .gif files, not {}",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+impl From<png::EncodingError> for ExportError {
+    fn from(e: png::EncodingError) -> Self {
+        ExportError::Png(e)
+    }
+}
+
+impl From<gif::EncodingError> for ExportError {
+    fn from(e: gif::EncodingError) -> Self {
+        ExportError::Gif(e)
+    }
+}
+
+const BACKGROUND: [u8; 4] = [0, 0, 0, 255];
+const FOREGROUND: [u8; 4] = [255, 255, 255, 255];
+
+// draws frames into an rgba image on the cpu. quads fill whole pixels and text uses the bitmap
+// font scaled to the nearest whole pixel, so a frame only ever has a handful of colours
+pub struct Raster {
+    width: u32,
+    height: u32,
+    // text is positioned in window pixels, this maps them onto the raster
+    scale: (f32, f32),
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32, window_size: (f32, f32)) -> Self {
+        let mut raster = Self {
+            width,
+            height,
+            scale: (1.0, 1.0),
+            pixels: vec![0; width as usize * height as usize * 4],
+        };
+        raster.reset(window_size);
+        raster
+    }
+
+    // clears the image for a new frame of a window this size
+    pub fn reset(&mut self, window_size: (f32, f32)) {
+        let (window_width, window_height) = window_size;
+        self.scale = if window_width > 0.0 && window_height > 0.0 {
+            (
+                self.width as f32 / window_width,
+                self.height as f32 / window_height,
+            )
+        } else {
+            (1.0, 1.0)
+        };
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&BACKGROUND);
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // fills a rectangle in raster pixels, clipped to the image
+    pub fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 4]) {
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = (x + width).min(self.width as i64);
+        let bottom = (y + height).min(self.height as i64);
+        // wholly off the image, left can be past the last pixel of the last row
+        if right <= left {
+            return;
+        }
+        for row in top..bottom {
+            let start = (row as usize * self.width as usize + left as usize) * 4;
+            for pixel in self.pixels[start..]
+                .chunks_exact_mut(4)
+                .take((right - left) as usize)
+            {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> Result<(), ExportError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), ExportError> {
+        let file = File::create(path)?;
+        self.write_png(BufWriter::new(file))
+    }
+}
+
+impl RenderSink for Raster {
+    fn push_quad(&mut self, quad: &Quad) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let radii = quad.size * 0.5;
+        let left = ((quad.position.x - radii.x + 1.0) * 0.5 * width).round();
+        let right = ((quad.position.x + radii.x + 1.0) * 0.5 * width).round();
+        let top = ((1.0 - quad.position.y - radii.y) * 0.5 * height).round();
+        let bottom = ((1.0 - quad.position.y + radii.y) * 0.5 * height).round();
+        // thin quads such as the debug lines still cover a pixel
+        self.fill(
+            left as i64,
+            top as i64,
+            (right - left).max(1.0) as i64,
+            (bottom - top).max(1.0) as i64,
+            FOREGROUND,
+        );
+    }
+
+    fn push_text(&mut self, text: &RenderText) {
+        let color = [
+            (text.color.x * text.color.w * 255.0) as u8,
+            (text.color.y * text.color.w * 255.0) as u8,
+            (text.color.z * text.color.w * 255.0) as u8,
+            255,
+        ];
+        // the text size is the height of a line, the font's cell is scaled to fill it
+        let pixel = ((text.size * self.scale.1 / font::CELL_HEIGHT as f32).round() as i64).max(1);
+        let cell_width = font::CELL_WIDTH as i64 * pixel;
+        let cell_height = font::CELL_HEIGHT as i64 * pixel;
+
+        let lines: Vec<&str> = text.text.lines().collect();
+        let x = (text.position.x * self.scale.0) as i64;
+        let mut y = (text.position.y * self.scale.1) as i64;
+        if text.centered {
+            y -= lines.len() as i64 * cell_height / 2;
+        }
+        for line in lines {
+            let length = line.chars().count() as i64;
+            let mut left = x;
+            if text.centered {
+                left -= length * cell_width / 2;
+            }
+            for (i, c) in line.chars().enumerate() {
+                let glyph_left = left + i as i64 * cell_width;
+                for (row, bits) in font::glyph(c).iter().enumerate() {
+                    for column in 0..font::GLYPH_WIDTH {
+                        if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) != 0 {
+                            self.fill(
+                                glyph_left + column as i64 * pixel,
+                                y + row as i64 * pixel,
+                                pixel,
+                                pixel,
+                                color,
+                            );
+                        }
+                    }
+                }
+            }
+            // the focused menu item is underlined
+            if text.focused {
+                self.fill(
+                    left,
+                    y + font::GLYPH_HEIGHT as i64 * pixel,
+                    length * cell_width - pixel,
+                    pixel,
+                    color,
+                );
+            }
+            y += cell_height;
+        }
+    }
+}
+
+// writes rasters as the frames of a looping gif
+pub struct GifWriter<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u16,
+    height: u16,
+    // in hundredths of a second, as gif has it
+    delay: u16,
+    palette: Vec<[u8; 3]>,
+    indices: Vec<u8>,
+    // frames with more colours than fit in a palette are quantized from this copy
+    scratch: Vec<u8>,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(
+        writer: W,
+        width: u32,
+        height: u32,
+        frame_time: Duration,
+    ) -> Result<Self, ExportError> {
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(ExportError::TooLarge(width, height));
+        }
+        let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(Self {
+            encoder,
+            width: width as u16,
+            height: height as u16,
+            delay: (frame_time.as_millis() / 10).max(1) as u16,
+            palette: Vec::new(),
+            indices: Vec::new(),
+            scratch: Vec::new(),
+        })
+    }
+
+    pub fn push(&mut self, raster: &Raster) -> Result<(), ExportError> {
+        assert_eq!(
+            (raster.width(), raster.height()),
+            (self.width as u32, self.height as u32)
+        );
+
+        let mut frame = if self.index_colors(raster.pixels()) {
+            let palette: Vec<u8> = self.palette.iter().flatten().copied().collect();
+            gif::Frame::from_palette_pixels(self.width, self.height, &self.indices, &palette, None)
+        } else {
+            self.scratch.clear();
+            self.scratch.extend_from_slice(raster.pixels());
+            gif::Frame::from_rgba_speed(self.width, self.height, &mut self.scratch, 10)
+        };
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+
+    // builds an exact palette for the frame, false if it has more than 256 colours
+    fn index_colors(&mut self, pixels: &[u8]) -> bool {
+        self.palette.clear();
+        self.indices.clear();
+        let mut last: Option<([u8; 3], u8)> = None;
+        for pixel in pixels.chunks_exact(4) {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            let index = match last {
+                Some((last_color, index)) if last_color == color => index,
+                _ => match self.palette.iter().position(|c| *c == color) {
+                    Some(index) => index as u8,
+                    None if self.palette.len() < 256 => {
+                        self.palette.push(color);
+                        (self.palette.len() - 1) as u8
+                    }
+                    None => return false,
+                },
+            };
+            last = Some((color, index));
+            self.indices.push(index);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(raster: &Raster, x: u32, y: u32) -> bool {
+        let start = ((y * raster.width() + x) * 4) as usize;
+        raster.pixels()[start..start + 4] != BACKGROUND
+    }
+
+    #[test]
+    fn text_past_the_corner_is_clipped() {
+        let mut raster = Raster::new(100, 50, (100.0, 50.0));
+        raster.push_text(&RenderText {
+            position: (90.0, 45.0).into(),
+            text: String::from("WWWW\nWWWW"),
+            focused: true,
+            ..Default::default()
+        });
+        assert!(lit(&raster, 90, 45));
+    }
+
+    #[test]
+    fn quads_past_the_edges_are_clipped() {
+        let mut raster = Raster::new(100, 50, (100.0, 50.0));
+        // centred on the bottom right corner, only its top left quarter is on the image
+        raster.push_quad(&Quad::new((1.0, -1.0).into(), (0.2, 0.4).into()));
+        assert!(lit(&raster, 99, 49));
+        assert!(lit(&raster, 95, 45));
+        assert!(!lit(&raster, 94, 49));
+        assert!(!lit(&raster, 99, 44));
+
+        // and one entirely off to the right draws nothing
+        raster.reset((100.0, 50.0));
+        raster.push_quad(&Quad::new((1.5, 0.0).into(), (0.2, 0.2).into()));
+        raster.fill(101, 49, 5, 5, FOREGROUND);
+        assert!(raster
+            .pixels()
+            .chunks_exact(4)
+            .all(|pixel| pixel == BACKGROUND));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::util;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RECORDS_FILE: &str = "records.json";
+const RECENT_COUNT: usize = 5;
+
+#[derive(Debug)]
+pub enum RecordsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for RecordsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordsError::Io(e) => write!(f, "could not access records: {}", e),
+            RecordsError::Parse(e) => write!(f, "invalid records: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RecordsError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchRecord {
+    pub date: u64,
+    pub mode: String,
+    pub arena: String,
+    pub players: [String; 2],
+    pub score: [u32; 2],
+    pub duration_secs: f32,
+    pub longest_rally: u32,
+}
+
+impl MatchRecord {
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    pub fn winner(&self) -> &str {
+        if self.score[0] > self.score[1] {
+            &self.players[0]
+        } else {
+            &self.players[1]
+        }
+    }
+
+    pub fn margin(&self) -> u32 {
+        self.score[0].max(self.score[1]) - self.score[0].min(self.score[1])
+    }
+}
+
+impl fmt::Display for MatchRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}  {}  {} {} - {} {}  {:.0}s",
+            format_date(self.date),
+            self.mode,
+            self.players[0],
+            self.score[0],
+            self.score[1],
+            self.players[1],
+            self.duration_secs
+        )
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Records {
+    pub matches: Vec<MatchRecord>,
+}
+
+impl Records {
+    pub fn load() -> Result<Records, RecordsError> {
+        let path = util::data_path(RECORDS_FILE);
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+        let source = std::fs::read_to_string(path).map_err(RecordsError::Io)?;
+        serde_json::from_str(&source).map_err(RecordsError::Parse)
+    }
+
+    pub fn save(&self) -> Result<(), RecordsError> {
+        let path = util::data_path(RECORDS_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(RecordsError::Io)?;
+        }
+        let source = serde_json::to_string_pretty(self).map_err(RecordsError::Parse)?;
+        std::fs::write(path, source).map_err(RecordsError::Io)
+    }
+
+    pub fn push(&mut self, record: MatchRecord) {
+        self.matches.push(record);
+    }
+
+    pub fn recent(&self) -> impl Iterator<Item = &MatchRecord> {
+        self.matches.iter().rev().take(RECENT_COUNT)
+    }
+
+    pub fn longest_rally(&self) -> Option<&MatchRecord> {
+        self.matches.iter().max_by_key(|record| record.longest_rally)
+    }
+
+    pub fn biggest_win(&self) -> Option<&MatchRecord> {
+        self.matches.iter().max_by_key(|record| record.margin())
+    }
+
+    pub fn fastest_match(&self) -> Option<&MatchRecord> {
+        self.matches
+            .iter()
+            .min_by(|a, b| a.duration_secs.total_cmp(&b.duration_secs))
+    }
+
+    pub fn summary(&self) -> String {
+        if self.matches.is_empty() {
+            return String::from("No matches played yet");
+        }
+
+        let mut lines = vec![String::from("Recent matches")];
+        lines.extend(self.recent().map(|record| record.to_string()));
+        lines.push(String::new());
+        lines.push(String::from("Best results"));
+        if let Some(record) = self.longest_rally() {
+            lines.push(format!(
+                "Longest rally: {} ({})",
+                record.longest_rally,
+                format_date(record.date)
+            ));
+        }
+        if let Some(record) = self.biggest_win() {
+            lines.push(format!(
+                "Biggest win: {} by {} ({})",
+                record.winner(),
+                record.margin(),
+                format_date(record.date)
+            ));
+        }
+        if let Some(record) = self.fastest_match() {
+            lines.push(format!(
+                "Fastest match: {:.0}s ({})",
+                record.duration_secs,
+                format_date(record.date)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+// days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
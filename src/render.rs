@@ -0,0 +1,143 @@
+// the game's own drawing types, so State and the systems build without dynamo_lib. with the
+// window feature they convert into dynamo_lib's types when drawing
+#[cfg(feature = "window")]
+use dynamo_lib::geometry::Geometry;
+#[cfg(feature = "window")]
+use dynamo_lib::renderer::render_text::TextRenderer;
+
+use serde::{Deserialize, Serialize};
+
+pub const UNBOUNDED_F32: f32 = f32::INFINITY;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quad {
+    pub position: cgmath::Vector2<f32>,
+    pub size: cgmath::Vector2<f32>,
+}
+
+impl Quad {
+    pub fn new(position: cgmath::Vector2<f32>, size: cgmath::Vector2<f32>) -> Self {
+        Self { position, size }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderText {
+    pub position: cgmath::Vector2<f32>,
+    pub bounds: cgmath::Vector2<f32>,
+    pub color: cgmath::Vector4<f32>,
+    pub text: String,
+    pub size: f32,
+    pub focused: bool,
+    pub centered: bool,
+}
+
+impl Default for RenderText {
+    fn default() -> Self {
+        Self {
+            position: (0.0, 0.0).into(),
+            bounds: (UNBOUNDED_F32, UNBOUNDED_F32).into(),
+            color: (1.0, 1.0, 1.0, 1.0).into(),
+            text: String::new(),
+            size: 16.0,
+            focused: false,
+            centered: false,
+        }
+    }
+}
+
+// whatever a frame gets drawn into. quads are in window space, from -1 to 1 on both axes, and
+// texts are positioned in pixels
+pub trait RenderSink {
+    fn push_quad(&mut self, quad: &Quad);
+    fn push_text(&mut self, text: &RenderText);
+}
+
+// keeps everything pushed to it, to compare what two frames drew or to hand a frame on later
+#[derive(Debug, Default, Clone)]
+pub struct Recording {
+    pub quads: Vec<Quad>,
+    pub texts: Vec<RenderText>,
+    // texts from before the last clear, their strings are reused so a recording that's cleared
+    // and drawn into every frame stops allocating
+    spare: Vec<RenderText>,
+}
+
+impl PartialEq for Recording {
+    fn eq(&self, other: &Self) -> bool {
+        self.quads == other.quads && self.texts == other.texts
+    }
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+        self.spare.append(&mut self.texts);
+    }
+}
+
+impl RenderSink for Recording {
+    fn push_quad(&mut self, quad: &Quad) {
+        self.quads.push(*quad);
+    }
+
+    fn push_text(&mut self, text: &RenderText) {
+        let copy = match self.spare.pop() {
+            Some(mut spare) => {
+                spare.text.clone_from(&text.text);
+                RenderText {
+                    text: spare.text,
+                    ..*text
+                }
+            }
+            None => text.clone(),
+        };
+        self.texts.push(copy);
+    }
+}
+
+// draws through dynamo_lib for the windowed game
+#[cfg(feature = "window")]
+pub struct DynamoSink<'a> {
+    pub geometry: &'a mut Geometry,
+    pub text_renderer: &'a mut TextRenderer,
+}
+
+#[cfg(feature = "window")]
+impl RenderSink for DynamoSink<'_> {
+    fn push_quad(&mut self, quad: &Quad) {
+        self.geometry.push_quad(&(*quad).into());
+    }
+
+    // the text renderer owns what it draws and is reset every frame, so it gets a copy
+    fn push_text(&mut self, text: &RenderText) {
+        self.text_renderer.push_render_text(text.into());
+    }
+}
+
+#[cfg(feature = "window")]
+impl From<Quad> for dynamo_lib::geometry::quad::Quad {
+    fn from(quad: Quad) -> Self {
+        dynamo_lib::geometry::quad::Quad::new(quad.position, quad.size)
+    }
+}
+
+#[cfg(feature = "window")]
+impl From<&RenderText> for dynamo_lib::renderer::render_text::RenderText {
+    fn from(text: &RenderText) -> Self {
+        dynamo_lib::renderer::render_text::RenderText {
+            position: text.position,
+            bounds: text.bounds,
+            color: text.color,
+            text: text.text.clone(),
+            size: text.size,
+            focused: text.focused,
+            centered: text.centered,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::env::{PaddleAction, PongEnv};
+use crate::state::{GameMode, State};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const REPLAY_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Empty,
+    Version(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "could not access replay: {}", e),
+            ReplayError::Parse(e) => write!(f, "invalid replay: {}", e),
+            ReplayError::Empty => write!(f, "invalid replay: the file is empty"),
+            ReplayError::Version(version) => {
+                write!(f, "invalid replay: unknown version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(e: serde_json::Error) -> Self {
+        ReplayError::Parse(e)
+    }
+}
+
+// the first line of a replay file. the match plays out the same from the seed as long as the
+// paddles are in the same places, so that's all the rest of the file holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub mode: GameMode,
+    pub arena: String,
+    pub points_to_win: u32,
+    pub seed: u64,
+}
+
+impl ReplayHeader {
+    pub fn new(state: &State, seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            mode: state.mode,
+            arena: state.arena.name.clone(),
+            points_to_win: state.rules.points_to_win,
+            seed,
+        }
+    }
+}
+
+// where both paddles were at the end of a serving or playing tick
+pub type ReplayFrame = [f32; 2];
+
+pub fn frame(state: &State) -> ReplayFrame {
+    [state.player1.position().y, state.player2.position().y]
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(header: ReplayHeader) -> Self {
+        Self {
+            header,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let mut recorder = ReplayRecorder::create(path, &self.header)?;
+        for frame in &self.frames {
+            recorder.push(*frame)?;
+        }
+        recorder.finish()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header: ReplayHeader = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err(ReplayError::Empty),
+        };
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::Version(header.version));
+        }
+
+        let mut frames = Vec::new();
+        for line in lines {
+            let line = line?;
+            if !line.trim().is_empty() {
+                frames.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Replay { header, frames })
+    }
+
+    // plays the match back without drawing it, returns the final score and the ticks it took.
+    // on_tick sees the match after every tick
+    pub fn play_back(&self, mut on_tick: impl FnMut(&PongEnv)) -> ((u32, u32), u64) {
+        let mut env = PongEnv::new();
+        env.set_points_to_win(self.header.points_to_win);
+        if !env.select_arena(&self.header.arena) {
+            eprintln!(
+                "unknown arena {}, playing on {}",
+                self.header.arena,
+                env.arena_name()
+            );
+        }
+        env.reset(self.header.seed);
+
+        let mut ticks = 0;
+        for frame in &self.frames {
+            if env.done() {
+                break;
+            }
+            env.step(PaddleAction::Stay, PaddleAction::Stay);
+            env.set_paddles(*frame);
+            on_tick(&env);
+            ticks += 1;
+        }
+        (env.score(), ticks)
+    }
+}
+
+// writes a replay as the match is played, one line per tick
+pub struct ReplayRecorder {
+    writer: BufWriter<File>,
+}
+
+impl ReplayRecorder {
+    pub fn create(path: impl AsRef<Path>, header: &ReplayHeader) -> Result<Self, ReplayError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self { writer })
+    }
+
+    pub fn record(&mut self, state: &State) -> Result<(), ReplayError> {
+        self.push(frame(state))
+    }
+
+    pub fn push(&mut self, frame: ReplayFrame) -> Result<(), ReplayError> {
+        serde_json::to_writer(&mut self.writer, &frame)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    // flushes what's left, dropping the recorder does the same but can't report a failure
+    pub fn finish(mut self) -> Result<(), ReplayError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// moves the paddles the way the recording says, one frame per tick
+pub struct ReplayPlayer {
+    replay: Replay,
+    tick: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, tick: 0 }
+    }
+
+    pub fn header(&self) -> &ReplayHeader {
+        &self.replay.header
+    }
+
+    // false once the recording has run out
+    pub fn apply(&mut self, state: &mut State) -> bool {
+        match self.replay.frames.get(self.tick) {
+            Some([y1, y2]) => {
+                state.player1.update_y_position(*y1);
+                state.player2.update_y_position(*y2);
+                self.tick += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
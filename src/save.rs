@@ -0,0 +1,164 @@
+use crate::arena::Arena;
+use crate::ball::Ball;
+use crate::player::Player;
+use crate::state::{GameMode, Rules, State};
+use crate::stats::MatchStats;
+use crate::util;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+const SAVE_FILE: &str = "saved_match.json";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnknownArena(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access saved match: {}", e),
+            SaveError::Parse(e) => write!(f, "invalid saved match: {}", e),
+            SaveError::UnknownArena(name) => {
+                write!(f, "saved match is in arena {}, which isn't loaded", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// the parts of State a match is made of, saved as they are. anything drawn or set every frame
+// is skipped on the types themselves
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedMatch {
+    pub mode: GameMode,
+    pub rules: Rules,
+    pub arena: Arena,
+    pub player1: Player,
+    pub player2: Player,
+    pub ball: Ball,
+    pub stats: MatchStats,
+}
+
+impl SavedMatch {
+    pub fn from_state(state: &State) -> SavedMatch {
+        SavedMatch {
+            mode: state.mode,
+            rules: state.rules,
+            arena: state.arena.clone(),
+            player1: state.player1.clone(),
+            player2: state.player2.clone(),
+            ball: state.ball.clone(),
+            stats: state.stats.clone(),
+        }
+    }
+
+    // restores the match as it was when it was saved, the caller decides which state to resume
+    // into. the arena has to be one of the loaded ones so the menu can show it
+    pub fn apply(&self, state: &mut State) -> Result<(), SaveError> {
+        let index = state
+            .arenas
+            .iter()
+            .position(|arena| arena.name == self.arena.name)
+            .ok_or_else(|| SaveError::UnknownArena(self.arena.name.clone()))?;
+        state.select_arena(index);
+        state.arena = self.arena.clone();
+
+        state.mode = self.mode;
+        state.rules = self.rules;
+        state.player1 = self.player1.clone();
+        state.player2 = self.player2.clone();
+        state.ball = self.ball.clone();
+        state.stats = self.stats.clone();
+        Ok(())
+    }
+
+    pub fn load() -> Result<Option<SavedMatch>, SaveError> {
+        let path = util::data_path(SAVE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let source = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+        serde_json::from_str(&source)
+            .map(Some)
+            .map_err(SaveError::Parse)
+    }
+
+    // whether there's a match to continue, a file that can't be read counts as none
+    pub fn exists() -> bool {
+        matches!(SavedMatch::load(), Ok(Some(_)))
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        let path = util::data_path(SAVE_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(SaveError::Io)?;
+        }
+        let source = serde_json::to_string_pretty(self).map_err(SaveError::Parse)?;
+        std::fs::write(path, source).map_err(SaveError::Io)
+    }
+
+    pub fn delete() -> Result<(), SaveError> {
+        let path = util::data_path(SAVE_FILE);
+        if path.exists() {
+            std::fs::remove_file(path).map_err(SaveError::Io)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(saved_match: &SavedMatch) -> SavedMatch {
+        serde_json::from_str(&serde_json::to_string(saved_match).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn restores_the_match() {
+        let mut state = State::new(Arena::builtin(), false);
+        state.select_arena(2);
+        for _ in 0..10 {
+            state.arena.advance();
+        }
+        state.player1.update_y_position(0.3);
+        state.player1.score = 2;
+        state.player2.score = 4;
+        state.ball.update_position((0.1, -0.2).into());
+        state.ball.velocity = (0.01, 0.02).into();
+        state.rules.points_to_win = 7;
+        let saved_match = round_trip(&SavedMatch::from_state(&state));
+
+        let mut restored = State::new(Arena::builtin(), false);
+        saved_match.apply(&mut restored).unwrap();
+        assert_eq!(restored.arena_index, 2);
+        assert_eq!(restored.arena.name, state.arena.name);
+        assert_eq!(
+            restored.arena.obstacles[0].position(),
+            state.arena.obstacles[0].position()
+        );
+        assert_eq!(restored.player1.position(), state.player1.position());
+        assert_eq!((restored.player1.score, restored.player2.score), (2, 4));
+        assert_eq!(restored.ball.position(), state.ball.position());
+        assert_eq!(restored.ball.velocity, state.ball.velocity);
+        assert_eq!(restored.rules.points_to_win, 7);
+    }
+
+    #[test]
+    fn reports_a_missing_arena() {
+        let mut state = State::new(Arena::builtin(), false);
+        state.arena.name = String::from("Gone");
+        let saved_match = round_trip(&SavedMatch::from_state(&state));
+
+        let mut restored = State::new(Arena::builtin(), false);
+        match saved_match.apply(&mut restored) {
+            Err(SaveError::UnknownArena(name)) => assert_eq!(name, "Gone"),
+            other => panic!("expected an unknown arena, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use crate::ai::{Ai, Difficulty};
+use crate::any;
+use crate::arena::Arena;
+use crate::attract::{AttractMode, ATTRACT_DELAY};
+use crate::bot::Bot;
+use crate::capture::Capture;
+use crate::event::Event;
+use crate::event_log::EventLog;
+use crate::gamepad::{self, Gamepads};
+use crate::input::{Action, Input};
+use crate::render::RenderSink;
+use crate::replay::{Replay, ReplayHeader, ReplayPlayer, ReplayRecorder};
+use crate::save::SavedMatch;
+use crate::simulation::Simulation;
+use crate::state::*;
+use crate::system::*;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
+
+// the whole game, menus and all, without a window. the windowed game and the terminal frontend
+// each feed it input, tick it and draw what it hands back
+pub struct Session {
+    pub input: Input,
+    events: Vec<Event>,
+    state: State,
+    menu_system: MenuSystem,
+    pause_system: PauseSystem,
+    simulation: Simulation,
+    game_over_system: GameOverSystem,
+    settings_system: SettingsSystem,
+    records_system: RecordsSystem,
+    visibility_system: VisibilitySystem,
+    gamepads: Gamepads,
+    capture: Capture,
+    attract_mode: Option<AttractMode>,
+    last_activity: Instant,
+    ais: [Option<Ai>; 2],
+    bots: [Option<Bot>; 2],
+    // every match starts from this seed, a random one each match when it's None
+    seed: Option<u64>,
+    record_path: Option<PathBuf>,
+    recorder: Option<ReplayRecorder>,
+    replay: Option<ReplayPlayer>,
+    event_log: Option<EventLog>,
+    // updates since the session was made
+    tick: u64,
+}
+
+impl Session {
+    // arenas and has_saved_match are loaded by the frontend, see State::new
+    pub fn new(arenas: Vec<Arena>, has_saved_match: bool) -> Self {
+        Self {
+            input: Input::new(),
+            events: Vec::new(),
+            state: State::new(arenas, has_saved_match),
+            menu_system: MenuSystem,
+            pause_system: PauseSystem,
+            simulation: Simulation::new(),
+            game_over_system: GameOverSystem,
+            settings_system: SettingsSystem,
+            records_system: RecordsSystem,
+            visibility_system: VisibilitySystem,
+            gamepads: Gamepads::new(gamepad::default_backend()),
+            capture: Capture::new(),
+            attract_mode: None,
+            last_activity: Instant::now(),
+            ais: [None, None],
+            bots: [None, None],
+            seed: None,
+            record_path: None,
+            recorder: None,
+            replay: None,
+            event_log: None,
+            tick: 0,
+        }
+    }
+
+    // a session with a replay goes straight into it, otherwise to the main menu
+    pub fn start(&mut self, window_size: (f32, f32)) {
+        self.state.resize(window_size);
+        self.menu_system.start(&mut self.state);
+        if self.replay.is_some() {
+            self.state.game_state = GameState::Serving;
+            self.begin_match();
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // what happened during the last update, for sounds and the like
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    // hands a paddle to an external program instead of the keyboard
+    pub fn set_bot(&mut self, player: usize, bot: Bot) {
+        self.bots[player] = Some(bot);
+    }
+
+    // hands a paddle to the built in opponent
+    pub fn set_ai(&mut self, player: usize, difficulty: Difficulty) {
+        self.ais[player] = Some(Ai::new(player, difficulty));
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.state.mode = mode;
+    }
+
+    pub fn set_points_to_win(&mut self, points: u32) {
+        self.state.rules.points_to_win = points.max(1);
+    }
+
+    // returns false when there's no arena with that name
+    pub fn select_arena(&mut self, name: &str) -> bool {
+        match self
+            .state
+            .arenas
+            .iter()
+            .position(|arena| arena.name == name)
+        {
+            Some(index) => {
+                self.state.select_arena(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // writes a replay of each match to this file, the last match played is the one kept
+    pub fn record_to(&mut self, path: impl Into<PathBuf>) {
+        self.record_path = Some(path.into());
+    }
+
+    // writes every event from here on to a json lines file
+    pub fn log_events_to(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        self.event_log = Some(EventLog::create(path.into(), self.state.game_state)?);
+        Ok(())
+    }
+
+    // plays a recorded match back instead of showing the menu, on the same rules and court
+    pub fn set_replay(&mut self, replay: Replay) {
+        let header = &replay.header;
+        self.seed = Some(header.seed);
+        self.state.mode = header.mode;
+        self.state.rules.points_to_win = header.points_to_win;
+        if !self.select_arena(&header.arena) {
+            eprintln!(
+                "unknown arena {}, playing on {}",
+                header.arena, self.state.arena.name
+            );
+        }
+        self.replay = Some(ReplayPlayer::new(replay));
+    }
+
+    pub fn is_quitting(&self) -> bool {
+        self.state.game_state == GameState::Quitting
+    }
+
+    pub fn resize(&mut self, window_size: (f32, f32)) {
+        self.state.resize(window_size);
+        if let Some(attract_mode) = &mut self.attract_mode {
+            attract_mode.resize(window_size);
+        }
+    }
+
+    pub fn focus_changed(&mut self, focus: bool) {
+        if !focus && self.state.game_state == GameState::Playing {
+            self.pause_system.start(&mut self.state);
+            self.state.pause_game();
+        }
+    }
+
+    pub fn draw(&mut self, sink: &mut impl RenderSink) {
+        draw_frame(&mut self.attract_mode, &mut self.state, sink);
+    }
+
+    // screenshots and recordings show the frame that was on screen when the key went down
+    fn update_capture(&mut self) {
+        if self.input.just_pressed(Action::Screenshot) {
+            self.capture.request_screenshot();
+        }
+        if self.input.just_pressed(Action::Record) {
+            self.capture.toggle_recording(self.state.window_size());
+        }
+        if let Some(raster) = self.capture.frame(self.state.window_size()) {
+            draw_frame(&mut self.attract_mode, &mut self.state, raster);
+            self.capture.finish_frame();
+        }
+    }
+
+    // runs the demo match once the main menu has been left alone, any input stops it again
+    fn update_attract_mode(&mut self) {
+        if self.input.active() {
+            self.last_activity = Instant::now();
+            if self.attract_mode.take().is_some() {
+                // the key that stops the demo shouldn't also press a menu item
+                self.input.clear();
+            }
+        }
+
+        if self.state.game_state != GameState::MainMenu {
+            self.attract_mode = None;
+            self.last_activity = Instant::now();
+            return;
+        }
+
+        if self.attract_mode.is_none() && self.last_activity.elapsed() > ATTRACT_DELAY {
+            self.attract_mode = Some(AttractMode::new(
+                self.state.arenas.clone(),
+                self.state.arena_index,
+                self.state.window_size(),
+            ));
+        }
+        if let Some(attract_mode) = &mut self.attract_mode {
+            attract_mode.update();
+        }
+    }
+
+    fn update_players(&mut self) {
+        if !any!(
+            self.state.game_state,
+            GameState::Serving,
+            GameState::Playing
+        ) {
+            return;
+        }
+
+        for ai in self.ais.iter_mut().flatten() {
+            ai.update(&self.state, &mut self.input);
+        }
+        for (player, slot) in self.bots.iter_mut().enumerate() {
+            if let Some(bot) = slot {
+                if let Err(e) = bot.update(&self.state, player as u32, &mut self.input) {
+                    eprintln!("player {} bot: {}", player + 1, e);
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    // a match starts the same way every time for the same seed, so a replay only needs the seed
+    // and where the paddles went
+    fn begin_match(&mut self) {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        self.state.rng = StdRng::seed_from_u64(seed);
+        self.state.ball.update_position((0.0, 0.0).into());
+        self.state.arena.reset();
+        self.state.replaying = self.replay.is_some();
+
+        if let (None, Some(path)) = (&self.replay, &self.record_path) {
+            let header = ReplayHeader::new(&self.state, seed);
+            match ReplayRecorder::create(path, &header) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => eprintln!("not recording match, {}", e),
+            }
+        }
+
+        self.simulation.serve(&mut self.state);
+    }
+
+    fn end_match(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(e) = recorder.finish() {
+                eprintln!("{}", e);
+            }
+        }
+        self.replay = None;
+        self.state.replaying = false;
+    }
+
+    fn step_match(&mut self) {
+        self.simulation
+            .step(&mut self.input, &mut self.state, &mut self.events);
+
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record(&self.state) {
+                eprintln!("stopped recording match, {}", e);
+                self.recorder = None;
+            }
+        }
+        // once the recording runs out the paddles are left to the players
+        if let Some(replay) = &mut self.replay {
+            if !replay.apply(&mut self.state) {
+                self.replay = None;
+            }
+        }
+    }
+
+    fn save_match(&mut self) {
+        // a replay can be watched again from the file, there's nothing to continue
+        if self.state.replaying {
+            return;
+        }
+        match SavedMatch::from_state(&self.state).save() {
+            Ok(()) => self.state.has_saved_match = true,
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    // picks up the saved match paused, or goes back to the menu if it can't be loaded. either
+    // way the file is gone afterwards so it can only be continued once
+    fn continue_match(&mut self) {
+        let resumed = match SavedMatch::load() {
+            Ok(Some(saved_match)) => match saved_match.apply(&mut self.state) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    false
+                }
+            },
+            Ok(None) => false,
+            Err(e) => {
+                eprintln!("{}", e);
+                false
+            }
+        };
+        if let Err(e) = SavedMatch::delete() {
+            eprintln!("{}", e);
+        }
+        self.state.has_saved_match = false;
+
+        if resumed {
+            self.pause_system.start(&mut self.state);
+        } else {
+            self.state.game_state = GameState::MainMenu;
+            self.menu_system.start(&mut self.state);
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.events.clear();
+        self.update_capture();
+
+        if self
+            .gamepads
+            .update(&mut self.input, &mut self.state.settings.gamepads)
+        {
+            self.state.set_gamepads(self.gamepads.connected.clone());
+        }
+
+        self.update_attract_mode();
+        self.update_players();
+
+        self.visibility_system
+            .update_state(&mut self.input, &mut self.state, &mut self.events);
+        let previous_state = self.state.game_state;
+        match previous_state {
+            GameState::MainMenu => {
+                self.menu_system
+                    .update_state(&mut self.input, &mut self.state, &mut self.events);
+            }
+            GameState::Serving | GameState::Playing => self.step_match(),
+            GameState::Paused => {
+                self.pause_system
+                    .update_state(&mut self.input, &mut self.state, &mut self.events);
+            }
+            GameState::GameOver => {
+                self.game_over_system.update_state(
+                    &mut self.input,
+                    &mut self.state,
+                    &mut self.events,
+                );
+            }
+            GameState::Settings => {
+                self.settings_system.update_state(
+                    &mut self.input,
+                    &mut self.state,
+                    &mut self.events,
+                );
+            }
+            GameState::Records => {
+                self.records_system.update_state(
+                    &mut self.input,
+                    &mut self.state,
+                    &mut self.events,
+                );
+            }
+            GameState::Quitting => {}
+        }
+
+        // logged before end_frame clears the presses and releases
+        if let Some(event_log) = &mut self.event_log {
+            let logged = event_log.log_tick(self.tick, &self.input, &self.events, &self.state);
+            if let Err(e) = logged {
+                eprintln!("stopped logging events, {}", e);
+                self.event_log = None;
+            }
+        }
+        self.input.end_frame();
+
+        // stats need this frame's events before a system starts on the next state
+        for event in &self.events {
+            self.state.stats.record(event);
+        }
+
+        match (previous_state, self.state.game_state) {
+            (GameState::MainMenu, GameState::Serving)
+            | (GameState::GameOver, GameState::Serving) => {
+                self.begin_match();
+            }
+            (GameState::Playing, GameState::GameOver) => {
+                self.game_over_system.start(&mut self.state);
+                self.end_match();
+            }
+            (GameState::GameOver, GameState::MainMenu)
+            | (GameState::Settings, GameState::MainMenu)
+            | (GameState::Records, GameState::MainMenu) => {
+                self.menu_system.start(&mut self.state);
+            }
+            (GameState::Serving, GameState::MainMenu)
+            | (GameState::Playing, GameState::MainMenu)
+            | (GameState::Paused, GameState::MainMenu) => {
+                self.save_match();
+                self.end_match();
+                self.menu_system.start(&mut self.state);
+            }
+            (GameState::Paused, GameState::Quitting) => {
+                self.save_match();
+                self.end_match();
+            }
+            (GameState::MainMenu, GameState::Paused) => {
+                self.continue_match();
+            }
+            (GameState::MainMenu, GameState::Settings) => {
+                self.settings_system.start(&mut self.state);
+            }
+            (GameState::MainMenu, GameState::Records) => {
+                self.records_system.start(&mut self.state);
+            }
+            _ => (),
+        }
+
+        self.tick += 1;
+    }
+}
+
+// draws the demo match, if one is running, and then the game on top
+fn draw_frame(
+    attract_mode: &mut Option<AttractMode>,
+    state: &mut State,
+    sink: &mut impl RenderSink,
+) {
+    if let Some(attract_mode) = attract_mode {
+        attract_mode.draw(sink);
+    }
+    state.refresh_scores();
+    state.draw(sink);
+}
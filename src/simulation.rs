@@ -0,0 +1,64 @@
+use crate::event::Event;
+use crate::input::Input;
+use crate::state::{GameState, State};
+use crate::system::*;
+
+// one tick of a match: the serve, the rally and the hand offs between them. menus, pausing and
+// the end of the match are left to whatever drives it, which also ends the input frame
+pub struct Simulation {
+    serving_system: ServingSystem,
+    play_system: PlaySystem,
+    arena_system: ArenaSystem,
+    ball_system: BallSystem,
+}
+
+impl Simulation {
+    pub fn new() -> Self {
+        Self {
+            serving_system: ServingSystem,
+            play_system: PlaySystem,
+            arena_system: ArenaSystem,
+            ball_system: BallSystem,
+        }
+    }
+
+    // puts the ball in the middle for the next serve, also how a match begins
+    pub fn serve(&mut self, state: &mut State) {
+        state.game_state = GameState::Serving;
+        self.serving_system.start(state);
+    }
+
+    // does nothing unless the state is serving or playing
+    pub fn step(&mut self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
+        let previous_state = state.game_state;
+        match previous_state {
+            GameState::Serving => {
+                self.serving_system.update_state(input, state, events);
+                self.play_system.update_state(input, state, events);
+            }
+            GameState::Playing => {
+                self.arena_system.update_state(input, state, events);
+                self.ball_system.update_state(input, state, events);
+                self.play_system.update_state(input, state, events);
+            }
+            _ => return,
+        }
+        state.stats.tick();
+
+        match (previous_state, state.game_state) {
+            (GameState::Serving, GameState::Playing) => {
+                self.play_system.start(state);
+            }
+            (GameState::Playing, GameState::Serving) => {
+                self.serving_system.start(state);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
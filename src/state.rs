@@ -1,28 +1,107 @@
+use crate::arena::Arena;
 use crate::ball::Ball;
+use crate::gamepad::GamepadId;
+use crate::input::DirectionPolicy;
+use crate::layout::Layout;
+use crate::menu::{Menu, MenuAction, MenuItem};
 use crate::player::Player;
-use dynamo_lib::geometry::Geometry;
-use dynamo_lib::renderer::render_text::{RenderText, TextRenderer, UNBOUNDED_F32};
+use crate::render::{Quad, RenderSink, RenderText, UNBOUNDED_F32};
+use crate::stats::MatchStats;
+use crate::util::{self, Crossing};
+use crate::world::{Viewport, COURT_HALF_WIDTH};
+use cgmath::InnerSpace;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+use std::fmt::Write;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum GameState {
   MainMenu,
   Serving,
   Playing,
   Paused,
   GameOver,
+  Settings,
+  Records,
   Quitting,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+  Versus,
+}
+
+impl GameMode {
+  pub const ALL: [GameMode; 1] = [GameMode::Versus];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      GameMode::Versus => "Versus",
+    }
+  }
+
+  // case doesn't matter, "versus" finds Versus
+  pub fn from_name(name: &str) -> Option<GameMode> {
+    GameMode::ALL
+      .iter()
+      .copied()
+      .find(|mode| mode.name().eq_ignore_ascii_case(name))
+  }
+}
+
+// the most points a match can be played to, the settings slider stops here
+pub const MAX_POINTS_TO_WIN: u32 = 21;
+
+// the assist guide's dots and the mark where the ball will arrive, in world units
+const ASSIST_DOT_SIZE: f32 = 0.015;
+const ASSIST_DOT_SPACING: f32 = 0.08;
+const ASSIST_MARK_HEIGHT: f32 = 0.1;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Rules {
+  pub points_to_win: u32,
+}
+
+impl Default for Rules {
+  fn default() -> Self {
+    Self { points_to_win: 5 }
+  }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Settings {
+  pub sound: bool,
+  // dots out where the ball is going, for beginners
+  pub assist: bool,
+  // per player, whether the paddle follows the mouse instead of the keyboard
+  pub mouse_control: [bool; 2],
+  // per player, the controller that drives their paddle
+  pub gamepads: [Option<GamepadId>; 2],
+  // per player, how holding up and down together is resolved
+  pub direction_policy: [DirectionPolicy; 2],
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      sound: true,
+      assist: false,
+      mouse_control: [false, false],
+      gamepads: [None, None],
+      direction_policy: [DirectionPolicy::LastWins, DirectionPolicy::LastWins],
+    }
+  }
+}
+
 pub struct PongText {
   pub render_text: RenderText,
+  pub layout: Layout,
   pub visible: bool,
 }
 
 impl PongText {
-  pub fn focused(&self) -> bool {
-    self.render_text.focused
-  }
-
   pub fn set_focus(&mut self, focused: bool) {
     self.render_text.focused = focused;
   }
@@ -30,130 +109,403 @@ impl PongText {
 
 pub struct State {
   pub game_state: GameState,
+  pub mode: GameMode,
+  pub rules: Rules,
+  pub settings: Settings,
+  pub has_saved_match: bool,
+  // a recorded match is playing back, it isn't added to the records
+  pub replaying: bool,
   pub player1: Player,
   pub player2: Player,
   pub ball: Ball,
+  pub arena: Arena,
+  pub arenas: Vec<Arena>,
+  pub arena_index: usize,
+  pub stats: MatchStats,
+  // ticks left before the ball is served
+  pub serve_ticks: u32,
+  // ticks left before the game over screen goes back to the main menu
+  pub game_over_ticks: u32,
+  // anything random in a match draws from this, so a match replays from its seed
+  pub rng: StdRng,
+  pub connected_gamepads: Vec<(GamepadId, String)>,
   pub title_text: PongText,
-  pub play_button: PongText,
-  pub quit_button: PongText,
+  pub main_menu: Menu,
+  pub pause_menu: Menu,
+  pub game_over_menu: Menu,
+  pub settings_menu: Menu,
   pub player1_score: PongText,
   pub player2_score: PongText,
   pub win_text: PongText,
+  pub stats_text: PongText,
+  pub records_text: PongText,
+  pub viewport: Viewport,
   window_size: (f32, f32),
+  // the scores the score texts were last written for
+  shown_scores: (u32, u32),
 }
 
 impl State {
-  pub fn new() -> Self {
+  // reads nothing from disk, the frontend loads the arenas and checks for a saved match. the
+  // first arena is played until another is picked, classic when there are none
+  pub fn new(mut arenas: Vec<Arena>, has_saved_match: bool) -> Self {
+    let rules = Rules::default();
+    let settings = Settings::default();
+    if arenas.is_empty() {
+      arenas.push(Arena::classic());
+    }
+    let policy_names: Vec<String> = DirectionPolicy::ALL
+      .iter()
+      .map(|policy| String::from(policy.name()))
+      .collect();
+    let arena_names = arenas.iter().map(|arena| arena.name.clone()).collect();
+
     Self {
       game_state: GameState::MainMenu,
-      player1: Player::new((-0.8, 0.0).into(), (0.05, 0.4).into()),
-      player2: Player::new((0.8, 0.0).into(), (0.05, 0.4).into()),
+      mode: GameMode::Versus,
+      rules,
+      settings,
+      has_saved_match,
+      replaying: false,
+      player1: Player::new((0.2 - COURT_HALF_WIDTH, 0.0).into(), (0.05, 0.4).into()),
+      player2: Player::new((COURT_HALF_WIDTH - 0.2, 0.0).into(), (0.05, 0.4).into()),
       ball: Ball::new((0.0, 0.0).into(), 0.05),
+      arena: arenas[0].clone(),
+      arenas,
+      arena_index: 0,
+      stats: MatchStats::new(),
+      serve_ticks: 0,
+      game_over_ticks: 0,
+      rng: StdRng::from_entropy(),
+      connected_gamepads: Vec::new(),
       title_text: PongText {
         visible: false,
+        layout: Layout::new((0.0, 0.0), (20.0, 20.0)),
         render_text: RenderText {
-          position: (20.0, 20.0).into(),
           color: (1.0, 1.0, 1.0, 1.0).into(),
           text: String::from("PONG"),
           size: 64.0,
           ..Default::default()
         },
       },
-      play_button: PongText {
+      main_menu: Menu::new(
+        Layout::new((0.0, 0.0), (40.0, 100.0)),
+        vec![
+          MenuItem::button("Continue", MenuAction::Continue),
+          MenuItem::button("Play", MenuAction::Play),
+          MenuItem::choice("Arena", MenuAction::Arena, arena_names, 0),
+          MenuItem::button("Settings", MenuAction::Settings),
+          MenuItem::button("Records", MenuAction::Records),
+          MenuItem::button("Quit", MenuAction::Quit),
+        ],
+      ),
+      pause_menu: Menu::new(
+        Layout::new((0.0, 0.0), (40.0, 100.0)),
+        vec![
+          MenuItem::button("Resume", MenuAction::Resume),
+          MenuItem::button("Main Menu", MenuAction::MainMenu),
+          MenuItem::button("Quit", MenuAction::Quit),
+        ],
+      ),
+      game_over_menu: Menu::new(
+        Layout::new((0.0, 1.0), (40.0, -140.0)),
+        vec![
+          MenuItem::button("Play Again", MenuAction::PlayAgain),
+          MenuItem::button("Main Menu", MenuAction::MainMenu),
+        ],
+      ),
+      settings_menu: Menu::new(
+        Layout::new((0.0, 0.0), (40.0, 100.0)),
+        vec![
+          MenuItem::slider(
+            "Points to win",
+            MenuAction::PointsToWin,
+            rules.points_to_win as i32,
+            1,
+            MAX_POINTS_TO_WIN as i32,
+          ),
+          MenuItem::toggle("Sound", MenuAction::Sound, settings.sound),
+          MenuItem::toggle("Assist", MenuAction::Assist, settings.assist),
+          MenuItem::toggle(
+            "Player 1 mouse",
+            MenuAction::Player1Mouse,
+            settings.mouse_control[0],
+          ),
+          MenuItem::toggle(
+            "Player 2 mouse",
+            MenuAction::Player2Mouse,
+            settings.mouse_control[1],
+          ),
+          MenuItem::choice(
+            "Player 1 up + down",
+            MenuAction::Player1Direction,
+            policy_names.clone(),
+            0,
+          ),
+          MenuItem::choice(
+            "Player 2 up + down",
+            MenuAction::Player2Direction,
+            policy_names,
+            0,
+          ),
+          MenuItem::choice(
+            "Player 1 controller",
+            MenuAction::Player1Gamepad,
+            vec![String::from("None")],
+            0,
+          ),
+          MenuItem::choice(
+            "Player 2 controller",
+            MenuAction::Player2Gamepad,
+            vec![String::from("None")],
+            0,
+          ),
+          MenuItem::button("Back", MenuAction::Back),
+        ],
+      ),
+      player1_score: PongText {
         visible: false,
+        layout: Layout::new((0.25, 0.0), (0.0, 20.0)),
         render_text: RenderText {
-          position: (40.0, 100.0).into(),
           color: (1.0, 1.0, 1.0, 1.0).into(),
-          text: String::from("Play"),
+          text: String::from("0"),
           size: 32.0,
           ..Default::default()
         },
       },
-      quit_button: PongText {
+      player2_score: PongText {
         visible: false,
+        layout: Layout::new((0.75, 0.0), (0.0, 20.0)),
         render_text: RenderText {
-          position: (40.0, 160.0).into(),
           color: (1.0, 1.0, 1.0, 1.0).into(),
-          text: String::from("Quit"),
+          text: String::from("0"),
           size: 32.0,
           ..Default::default()
         },
       },
-      player1_score: PongText {
+      win_text: PongText {
         visible: false,
+        layout: Layout::new((0.5, 0.5), (0.0, 0.0)),
         render_text: RenderText {
-          // position: (render.width() * 0.25, 20.0).into(),
-          position: (20.0, 20.0).into(),
-          color: (1.0, 1.0, 1.0, 1.0).into(),
-          text: String::from("0"),
+          bounds: (UNBOUNDED_F32, UNBOUNDED_F32).into(),
           size: 32.0,
+          centered: true,
           ..Default::default()
         },
       },
-      player2_score: PongText {
+      stats_text: PongText {
         visible: false,
+        layout: Layout::new((0.5, 0.5), (0.0, 80.0)),
         render_text: RenderText {
-          // position: (render.width() * 0.75, 20.0).into(),
-          position: (120.0, 20.0).into(),
           color: (1.0, 1.0, 1.0, 1.0).into(),
-          text: String::from("0"),
-          size: 32.0,
+          bounds: (UNBOUNDED_F32, UNBOUNDED_F32).into(),
+          size: 24.0,
+          centered: true,
           ..Default::default()
         },
       },
-      win_text: PongText {
+      records_text: PongText {
         visible: false,
+        layout: Layout::new((0.0, 0.0), (40.0, 100.0)),
         render_text: RenderText {
-          // position: (render.width() * 0.5, render.height() * 0.5).into(),
-          position: (200.0, 200.0).into(),
-          bounds: (UNBOUNDED_F32, UNBOUNDED_F32).into(),
-          size: 32.0,
-          centered: true,
+          color: (1.0, 1.0, 1.0, 1.0).into(),
+          size: 24.0,
           ..Default::default()
         },
       },
+      viewport: Viewport::new((0.0, 0.0)),
       window_size: (0.0, 0.0),
+      shown_scores: (0, 0),
     }
   }
 
-  pub fn initialize(&mut self, geometry: &mut Geometry, text_renderer: &mut TextRenderer) {
-    self.update_geometry(geometry);
-    self.update_text(text_renderer);
+  // pushes the court and then every visible text, the same way whatever it's drawn into
+  pub fn draw(&self, sink: &mut impl RenderSink) {
+    for quad in self.quads() {
+      sink.push_quad(&quad);
+    }
+    if self.settings.assist {
+      for quad in self.assist_quads() {
+        sink.push_quad(&self.viewport.quad(&quad));
+      }
+    }
+    for text in self.render_texts() {
+      sink.push_text(text);
+    }
   }
 
-  pub fn update(&self, geometry: &mut Geometry, text_renderer: &mut TextRenderer) {
-    self.update_geometry(geometry);
-    self.update_text(text_renderer);
+  // everything visible on the court, back to front and already mapped onto the window
+  fn quads(&self) -> impl Iterator<Item = Quad> + '_ {
+    let blocks = self
+      .arena
+      .blocks()
+      .filter(move |_| self.arena.visible)
+      .map(|block| &block.quad);
+    let players = IntoIterator::into_iter([&self.player1, &self.player2])
+      .filter(|player| player.visible)
+      .map(|player| &player.quad);
+    let ball = Some(&self.ball.quad).filter(|_| self.ball.visible);
+    blocks
+      .chain(players)
+      .chain(ball)
+      .map(move |quad| self.viewport.quad(quad))
   }
 
-  fn update_geometry(&self, geometry: &mut Geometry) {
-    if self.player1.visible {
-      geometry.push_quad(&self.player1.quad);
+  // where the ball will reach the paddle it's heading for, if nobody touches it first
+  pub fn predicted_crossing(&self) -> Option<Crossing> {
+    if self.game_state != GameState::Playing {
+      return None;
     }
+    let paddle = if self.ball.velocity.x < 0.0 {
+      &self.player1
+    } else {
+      &self.player2
+    };
+    util::predict_crossing(&self.ball, &self.arena, paddle.position().x)
+  }
+
+  // a dotted line along the ball's path and a mark where it will arrive, in world space
+  fn assist_quads(&self) -> Vec<Quad> {
+    let crossing = match self.predicted_crossing() {
+      Some(crossing) => crossing,
+      None => return Vec::new(),
+    };
 
-    if self.player2.visible {
-      geometry.push_quad(&self.player2.quad);
+    let mut quads = Vec::new();
+    let mut from = self.ball.position();
+    // how far along the current leg the next dot goes, carried over so the spacing is even
+    let mut next_dot = ASSIST_DOT_SPACING;
+    for &to in crossing.bounces.iter().chain(Some(&crossing.position)) {
+      let length = (to - from).magnitude();
+      while next_dot < length {
+        let position = from + (to - from) * (next_dot / length);
+        quads.push(Quad::new(
+          position,
+          (ASSIST_DOT_SIZE, ASSIST_DOT_SIZE).into(),
+        ));
+        next_dot += ASSIST_DOT_SPACING;
+      }
+      next_dot -= length;
+      from = to;
     }
+    quads.push(Quad::new(
+      crossing.position,
+      (ASSIST_DOT_SIZE, ASSIST_MARK_HEIGHT).into(),
+    ));
+    quads
+  }
 
-    if self.ball.visible {
-      geometry.push_quad(&self.ball.quad);
+  // every visible text, menus last
+  fn render_texts(&self) -> impl Iterator<Item = &RenderText> {
+    let texts = self.texts();
+    let menus = self.menus();
+    IntoIterator::into_iter(texts)
+      .filter(|text| text.visible)
+      .chain(IntoIterator::into_iter(menus).flat_map(|menu| menu.texts()))
+      .map(|text| &text.render_text)
+  }
+
+  // the simulation only touches the numbers, the texts are rewritten here when drawing and only
+  // when a score changed, so headless runs never format strings
+  pub fn refresh_scores(&mut self) {
+    let scores = (self.player1.score, self.player2.score);
+    if scores == self.shown_scores {
+      return;
+    }
+    self.shown_scores = scores;
+    for (text, score) in [
+      (&mut self.player1_score, scores.0),
+      (&mut self.player2_score, scores.1),
+    ]
+    .iter_mut()
+    {
+      text.render_text.text.clear();
+      let _ = write!(text.render_text.text, "{}", score);
     }
   }
 
-  fn update_text(&self, text_renderer: &mut TextRenderer) {
-    for text in vec![
+  fn menus(&self) -> [&Menu; 4] {
+    [
+      &self.main_menu,
+      &self.pause_menu,
+      &self.game_over_menu,
+      &self.settings_menu,
+    ]
+  }
+
+  fn texts(&self) -> [&PongText; 6] {
+    [
       &self.title_text,
-      &self.play_button,
-      &self.quit_button,
       &self.player1_score,
       &self.player2_score,
       &self.win_text,
+      &self.stats_text,
+      &self.records_text,
+    ]
+  }
+
+  fn texts_mut(&mut self) -> [&mut PongText; 6] {
+    [
+      &mut self.title_text,
+      &mut self.player1_score,
+      &mut self.player2_score,
+      &mut self.win_text,
+      &mut self.stats_text,
+      &mut self.records_text,
+    ]
+  }
+
+  pub fn resize(&mut self, window_size: (f32, f32)) {
+    self.window_size = window_size;
+    self.viewport = Viewport::new(window_size);
+    for text in self.texts_mut().iter_mut() {
+      text.render_text.position = text.layout.position(window_size);
+    }
+    for menu in [
+      &mut self.main_menu,
+      &mut self.pause_menu,
+      &mut self.game_over_menu,
+      &mut self.settings_menu,
     ]
-    .iter()
+    .iter_mut()
     {
-      if text.visible {
-        text_renderer.push_render_text(text.render_text.clone());
-      }
+      menu.layout(window_size);
+    }
+  }
+
+  pub fn window_size(&self) -> (f32, f32) {
+    self.window_size
+  }
+
+  pub fn select_arena(&mut self, index: usize) {
+    self.arena_index = index % self.arenas.len();
+    self.arena = self.arenas[self.arena_index].clone();
+    self
+      .main_menu
+      .set_selected(MenuAction::Arena, self.arena_index);
+  }
+
+  // rebuilds the controller choices after a pad is plugged in or removed
+  pub fn set_gamepads(&mut self, connected: Vec<(GamepadId, String)>) {
+    self.connected_gamepads = connected;
+    let mut options = vec![String::from("None")];
+    options.extend(self.connected_gamepads.iter().map(|(_, name)| name.clone()));
+
+    for (player, action) in [MenuAction::Player1Gamepad, MenuAction::Player2Gamepad]
+      .iter()
+      .enumerate()
+    {
+      let selected = self.settings.gamepads[player]
+        .and_then(|id| {
+          self
+            .connected_gamepads
+            .iter()
+            .position(|(connected, _)| *connected == id)
+        })
+        .map_or(0, |index| index + 1);
+      self
+        .settings_menu
+        .set_options(*action, options.clone(), selected);
     }
   }
 
@@ -163,3 +515,161 @@ impl State {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::input::Input;
+  use crate::render::Recording;
+  use crate::system::*;
+
+  type Drawn = (
+    Vec<((f32, f32), (f32, f32))>,
+    Vec<(String, (f32, f32), bool)>,
+  );
+
+  // draws a fresh 800x600 game once it's been moved into the given state
+  fn draw_in(game_state: GameState, setup: impl FnOnce(&mut State)) -> Drawn {
+    let mut state = State::new(Arena::builtin(), false);
+    state.resize((800.0, 600.0));
+    // keeps the game over screen from writing to the records
+    state.replaying = true;
+    state.game_state = game_state;
+    setup(&mut state);
+    VisibilitySystem.update_state(&mut Input::new(), &mut state, &mut Vec::new());
+    state.refresh_scores();
+
+    let mut recording = Recording::new();
+    state.draw(&mut recording);
+    let quads = recording
+      .quads
+      .iter()
+      .map(|quad| (quad.position.into(), quad.size.into()))
+      .collect();
+    let texts = recording
+      .texts
+      .iter()
+      .map(|text| (text.text.clone(), text.position.into(), text.focused))
+      .collect();
+    (quads, texts)
+  }
+
+  fn texts(texts: &[(&str, (f32, f32), bool)]) -> Vec<(String, (f32, f32), bool)> {
+    texts
+      .iter()
+      .map(|(text, position, focused)| (String::from(*text), *position, *focused))
+      .collect()
+  }
+
+  #[test]
+  fn draws_the_main_menu() {
+    let (quads, drawn) = draw_in(GameState::MainMenu, |state| MenuSystem.start(state));
+    assert!(quads.is_empty());
+    assert_eq!(
+      drawn,
+      texts(&[
+        ("PONG", (20.0, 20.0), false),
+        ("Continue", (40.0, 100.0), false),
+        ("Play", (40.0, 160.0), true),
+        ("Arena: < Classic >", (40.0, 220.0), false),
+        ("Settings", (40.0, 280.0), false),
+        ("Records", (40.0, 340.0), false),
+        ("Quit", (40.0, 400.0), false),
+      ])
+    );
+  }
+
+  #[test]
+  fn draws_the_serve() {
+    let (quads, drawn) = draw_in(GameState::Serving, |state| ServingSystem.start(state));
+    assert_eq!(
+      quads,
+      vec![
+        ((-0.85, 0.0), (0.0375, 0.4)),
+        ((0.85, 0.0), (0.0375, 0.4)),
+        ((0.0, 0.0), (0.0375, 0.05)),
+      ]
+    );
+    assert_eq!(
+      drawn,
+      texts(&[("0", (200.0, 20.0), false), ("0", (600.0, 20.0), false)])
+    );
+  }
+
+  #[test]
+  fn draws_a_match_in_play() {
+    let (quads, drawn) = draw_in(GameState::Playing, |state| {
+      state.select_arena(1);
+      state.player1.update_y_position(0.3);
+      state.player2.update_y_position(-0.5);
+      state.ball.update_position((0.2, -0.1).into());
+      state.player1.score = 2;
+      state.player2.score = 1;
+    });
+    // the arena's blocks first, then the paddles and the ball, squeezed onto a wide window
+    assert_eq!(
+      quads,
+      vec![
+        ((0.0, 0.55), (0.06, 0.3)),
+        ((0.0, -0.55), (0.06, 0.3)),
+        ((-0.85, 0.3), (0.0375, 0.4)),
+        ((0.85, -0.5), (0.0375, 0.4)),
+        ((0.15, -0.1), (0.0375, 0.05)),
+      ]
+    );
+    assert_eq!(
+      drawn,
+      texts(&[("2", (200.0, 20.0), false), ("1", (600.0, 20.0), false)])
+    );
+  }
+
+  #[test]
+  fn draws_the_pause_menu() {
+    let (quads, drawn) = draw_in(GameState::Paused, |state| PauseSystem.start(state));
+    assert!(quads.is_empty());
+    assert_eq!(
+      drawn,
+      texts(&[
+        ("Paused", (20.0, 20.0), false),
+        ("Resume", (40.0, 100.0), true),
+        ("Main Menu", (40.0, 160.0), false),
+        ("Quit", (40.0, 220.0), false),
+      ])
+    );
+  }
+
+  #[test]
+  fn draws_the_game_over_screen() {
+    let (quads, drawn) = draw_in(GameState::GameOver, |state| {
+      state.player1.score = 5;
+      state.player2.score = 3;
+      GameOverSystem.start(state);
+    });
+    // the ball is gone once the match is over
+    assert_eq!(
+      quads,
+      vec![((-0.85, 0.0), (0.0375, 0.4)), ((0.85, 0.0), (0.0375, 0.4))]
+    );
+    assert_eq!(
+      drawn,
+      texts(&[
+        ("5", (200.0, 20.0), false),
+        ("3", (600.0, 20.0), false),
+        ("Player 1 wins!", (400.0, 300.0), false),
+        (
+          concat!(
+            "Paddle hits: 0 - 0\n",
+            "Points won on serve: 0 - 0\n",
+            "Average rally won: 0.0 - 0.0\n",
+            "Longest rally won: 0 - 0\n",
+            "Time in play: 0s of 0s",
+          ),
+          (400.0, 380.0),
+          false
+        ),
+        ("Play Again", (40.0, 460.0), true),
+        ("Main Menu", (40.0, 520.0), false),
+      ])
+    );
+  }
+}
@@ -0,0 +1,203 @@
+use crate::event::Event;
+use crate::util;
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub paddle_hits: u32,
+    pub points: u32,
+    pub points_on_serve: u32,
+    // a rally counts for the player who won the point
+    pub rallies: u32,
+    pub rally_hits: u32,
+    pub longest_rally: u32,
+}
+
+impl PlayerStats {
+    pub fn average_rally(&self) -> f32 {
+        if self.rallies == 0 {
+            0.0
+        } else {
+            self.rally_hits as f32 / self.rallies as f32
+        }
+    }
+}
+
+// counted in simulation ticks rather than wall clock time, so a paused match doesn't add up and
+// a replay or PongEnv run ends with the same numbers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchStats {
+    pub ticks: u32,
+    // ticks between each serve and the point it led to
+    pub ticks_in_play: u32,
+    pub players: [PlayerStats; 2],
+    pub server: Option<u32>,
+    pub rally: u32,
+    pub longest_rally: u32,
+    // the tick the ball was served on, while a rally is going
+    serve_tick: Option<u32>,
+}
+
+impl MatchStats {
+    pub fn new() -> Self {
+        Self {
+            ticks: 0,
+            ticks_in_play: 0,
+            players: [Default::default(); 2],
+            server: None,
+            rally: 0,
+            longest_rally: 0,
+            serve_tick: None,
+        }
+    }
+
+    // called once for every tick the match is simulated
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        match *event {
+            Event::Serve(player) => {
+                self.server = Some(player);
+                self.serve_tick = Some(self.ticks);
+            }
+            Event::PaddleHit { player, .. } => {
+                self.players[player as usize].paddle_hits += 1;
+                self.rally += 1;
+                self.longest_rally = self.longest_rally.max(self.rally);
+            }
+            Event::Score(player) => {
+                let stats = &mut self.players[player as usize];
+                stats.points += 1;
+                if self.server == Some(player) {
+                    stats.points_on_serve += 1;
+                }
+                stats.rallies += 1;
+                stats.rally_hits += self.rally;
+                stats.longest_rally = stats.longest_rally.max(self.rally);
+                if let Some(serve_tick) = self.serve_tick.take() {
+                    self.ticks_in_play += self.ticks - serve_tick;
+                }
+                self.rally = 0;
+            }
+            _ => (),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        ticks_to_duration(self.ticks)
+    }
+
+    pub fn time_in_play(&self) -> Duration {
+        ticks_to_duration(self.ticks_in_play)
+    }
+
+    pub fn summary(&self) -> String {
+        let [p1, p2] = &self.players;
+        [
+            format!("Paddle hits: {} - {}", p1.paddle_hits, p2.paddle_hits),
+            format!(
+                "Points won on serve: {} - {}",
+                p1.points_on_serve, p2.points_on_serve
+            ),
+            format!(
+                "Average rally won: {:.1} - {:.1}",
+                p1.average_rally(),
+                p2.average_rally()
+            ),
+            format!(
+                "Longest rally won: {} - {}",
+                p1.longest_rally, p2.longest_rally
+            ),
+            format!(
+                "Time in play: {:.0}s of {:.0}s",
+                self.time_in_play().as_secs_f32(),
+                self.duration().as_secs_f32()
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+fn ticks_to_duration(ticks: u32) -> Duration {
+    Duration::from_secs(u64::from(ticks)) / util::TICKS_PER_SECOND
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(player: u32) -> Event {
+        Event::PaddleHit {
+            player,
+            position: (0.0, 0.0).into(),
+            speed: util::BALL_SPEED,
+            contact: 0.0,
+        }
+    }
+
+    // plays out the events with the given number of ticks before each one
+    fn play(stats: &mut MatchStats, script: &[(u32, Event)]) {
+        for (ticks, event) in script {
+            for _ in 0..*ticks {
+                stats.tick();
+            }
+            stats.record(event);
+        }
+    }
+
+    #[test]
+    fn counts_hits_serves_and_rallies() {
+        let mut stats = MatchStats::new();
+        play(
+            &mut stats,
+            &[
+                // player 1 serves, three hits and player 2 wins the point
+                (0, Event::Serve(0)),
+                (10, hit(1)),
+                (10, hit(0)),
+                (10, hit(1)),
+                (10, Event::Score(1)),
+                // player 2 serves and wins it straight away
+                (120, Event::Serve(1)),
+                (40, Event::Score(1)),
+                // player 1 serves and wins after a single hit
+                (120, Event::Serve(0)),
+                (10, hit(1)),
+                (30, Event::Score(0)),
+            ],
+        );
+
+        let [p1, p2] = stats.players;
+        assert_eq!((p1.paddle_hits, p2.paddle_hits), (1, 3));
+        assert_eq!((p1.points, p2.points), (1, 2));
+        assert_eq!((p1.points_on_serve, p2.points_on_serve), (1, 1));
+        assert_eq!((p1.rallies, p2.rallies), (1, 2));
+        assert_eq!(p1.average_rally(), 1.0);
+        assert_eq!(p2.average_rally(), 1.5);
+        assert_eq!((p1.longest_rally, p2.longest_rally), (1, 3));
+        assert_eq!(stats.longest_rally, 3);
+    }
+
+    #[test]
+    fn counts_time_in_play_per_rally() {
+        let mut stats = MatchStats::new();
+        play(
+            &mut stats,
+            &[
+                (0, Event::Serve(0)),
+                (60, Event::Score(1)),
+                (120, Event::Serve(1)),
+                (30, Event::Score(0)),
+            ],
+        );
+
+        // both rallies count whoever won them, the serve delay in between doesn't
+        assert_eq!(stats.ticks_in_play, 90);
+        assert_eq!(stats.time_in_play(), Duration::from_millis(1500));
+        assert_eq!(stats.duration(), Duration::from_millis(3500));
+    }
+}
@@ -1,8 +1,14 @@
 use crate::any;
-use crate::input::Input;
-use crate::pong_game::*;
+use crate::event::Event;
+use crate::input::{Action, DirectionPolicy, Input};
+use crate::menu::MenuAction;
+use crate::records::{MatchRecord, Records};
 use crate::state::*;
+use crate::stats::MatchStats;
 use crate::util;
+use crate::world::{COURT_HALF_HEIGHT, COURT_HALF_WIDTH};
+use cgmath::InnerSpace;
+use rand::Rng;
 
 pub trait System {
   #[allow(unused_variables)]
@@ -24,14 +30,23 @@ impl System for VisibilitySystem {
     state.player1_score.visible = is_in_game;
     state.player2.visible = is_in_game;
     state.player2_score.visible = is_in_game;
+    state.arena.visible = is_in_game;
 
-    state.title_text.visible =
-      state.game_state == GameState::MainMenu || state.game_state == GameState::Paused;
-    state.play_button.visible =
-      state.game_state == GameState::MainMenu || state.game_state == GameState::Paused;
-    state.quit_button.visible = state.game_state == GameState::MainMenu;
+    state.title_text.visible = any!(
+      state.game_state,
+      GameState::MainMenu,
+      GameState::Paused,
+      GameState::Records,
+      GameState::Settings
+    );
+    state.main_menu.visible = state.game_state == GameState::MainMenu;
+    state.pause_menu.visible = state.game_state == GameState::Paused;
+    state.game_over_menu.visible = state.game_state == GameState::GameOver;
+    state.settings_menu.visible = state.game_state == GameState::Settings;
+    state.records_text.visible = state.game_state == GameState::Records;
 
     state.win_text.visible = state.game_state == GameState::GameOver;
+    state.stats_text.visible = state.game_state == GameState::GameOver;
   }
 }
 
@@ -44,34 +59,145 @@ impl System for MenuSystem {
     state.player2.score = 0;
     state.player1.update_y_position(0.0);
     state.player2.update_y_position(0.0);
-    state.play_button.render_text.focused = true;
-    state.quit_button.render_text.focused = false;
+    state.arena.reset();
+    state.title_text.render_text.text = String::from("PONG");
+    state
+      .main_menu
+      .set_enabled(MenuAction::Continue, state.has_saved_match);
+    state.main_menu.reset_focus();
   }
 
   fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
-    if input.esc_pressed {
+    if input.just_pressed(Action::Back) {
       events.push(Event::ButtonPressed);
       state.game_state = GameState::Quitting;
 
-      input.esc_pressed = false;
+      input.consume(Action::Back);
     }
 
-    if state.play_button.focused() && input.ui_down_pressed() {
-      events.push(Event::FocusChanged);
-      state.play_button.set_focus(false);
-      state.quit_button.set_focus(true);
-    } else if state.quit_button.focused() && input.ui_up_pressed() {
-      events.push(Event::FocusChanged);
-      state.quit_button.set_focus(false);
-      state.play_button.set_focus(true);
+    match state.main_menu.update(input, events) {
+      // the session loads the saved match when the state changes
+      Some(MenuAction::Continue) => state.game_state = GameState::Paused,
+      Some(MenuAction::Play) => {
+        state.stats = MatchStats::new();
+        state.game_state = GameState::Serving;
+      }
+      Some(MenuAction::Arena) => {
+        if let Some(index) = state.main_menu.selected(MenuAction::Arena) {
+          state.select_arena(index);
+        }
+      }
+      Some(MenuAction::Settings) => state.game_state = GameState::Settings,
+      Some(MenuAction::Records) => state.game_state = GameState::Records,
+      Some(MenuAction::Quit) => state.game_state = GameState::Quitting,
+      _ => (),
     }
+  }
+}
 
-    if state.play_button.focused() && input.enter_pressed {
+#[derive(Debug)]
+pub struct SettingsSystem;
+
+impl System for SettingsSystem {
+  fn start(&mut self, state: &mut State) {
+    state.title_text.render_text.text = String::from("Settings");
+    state
+      .settings_menu
+      .set_slider(MenuAction::PointsToWin, state.rules.points_to_win as i32);
+    state.settings_menu.reset_focus();
+  }
+
+  fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
+    if input.just_pressed(Action::Back) {
       events.push(Event::ButtonPressed);
-      state.game_state = GameState::Serving;
-    } else if state.quit_button.focused() && input.enter_pressed {
+      state.game_state = GameState::MainMenu;
+
+      input.consume(Action::Back);
+    }
+
+    match state.settings_menu.update(input, events) {
+      Some(MenuAction::PointsToWin) => {
+        if let Some(value) = state.settings_menu.slider_value(MenuAction::PointsToWin) {
+          state.rules.points_to_win = value as u32;
+        }
+      }
+      Some(MenuAction::Sound) => {
+        if let Some(on) = state.settings_menu.toggle_value(MenuAction::Sound) {
+          state.settings.sound = on;
+        }
+      }
+      Some(MenuAction::Assist) => {
+        if let Some(on) = state.settings_menu.toggle_value(MenuAction::Assist) {
+          state.settings.assist = on;
+        }
+      }
+      Some(MenuAction::Player1Mouse) => {
+        if let Some(on) = state.settings_menu.toggle_value(MenuAction::Player1Mouse) {
+          state.settings.mouse_control[0] = on;
+        }
+      }
+      Some(MenuAction::Player2Mouse) => {
+        if let Some(on) = state.settings_menu.toggle_value(MenuAction::Player2Mouse) {
+          state.settings.mouse_control[1] = on;
+        }
+      }
+      Some(MenuAction::Player1Direction) => {
+        set_direction_policy(state, 0, MenuAction::Player1Direction)
+      }
+      Some(MenuAction::Player2Direction) => {
+        set_direction_policy(state, 1, MenuAction::Player2Direction)
+      }
+      Some(MenuAction::Player1Gamepad) => assign_gamepad(state, 0, MenuAction::Player1Gamepad),
+      Some(MenuAction::Player2Gamepad) => assign_gamepad(state, 1, MenuAction::Player2Gamepad),
+      Some(MenuAction::Back) => state.game_state = GameState::MainMenu,
+      _ => (),
+    }
+  }
+}
+
+fn set_direction_policy(state: &mut State, player: usize, action: MenuAction) {
+  if let Some(index) = state.settings_menu.selected(action) {
+    state.settings.direction_policy[player] = DirectionPolicy::ALL[index];
+  }
+}
+
+// the first option is "None", the rest follow the connected pads in order
+fn assign_gamepad(state: &mut State, player: usize, action: MenuAction) {
+  let id = state
+    .settings_menu
+    .selected(action)
+    .and_then(|index| index.checked_sub(1))
+    .and_then(|index| state.connected_gamepads.get(index))
+    .map(|(id, _)| *id);
+  state.settings.gamepads[player] = id;
+
+  // a pad can only drive one paddle
+  let other = 1 - player;
+  if id.is_some() && state.settings.gamepads[other] == id {
+    state.settings.gamepads[other] = None;
+    let connected = state.connected_gamepads.clone();
+    state.set_gamepads(connected);
+  }
+}
+
+#[derive(Debug)]
+pub struct RecordsSystem;
+
+impl System for RecordsSystem {
+  fn start(&mut self, state: &mut State) {
+    state.title_text.render_text.text = String::from("Records");
+    state.records_text.render_text.text = match Records::load() {
+      Ok(records) => records.summary(),
+      Err(e) => format!("{}", e),
+    };
+  }
+
+  fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
+    if input.just_pressed(Action::Back) || input.just_pressed(Action::Confirm) {
       events.push(Event::ButtonPressed);
-      state.game_state = GameState::Quitting;
+      state.game_state = GameState::MainMenu;
+
+      input.clear();
     }
   }
 }
@@ -81,67 +207,84 @@ pub struct PlaySystem;
 
 impl System for PlaySystem {
   fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
-    if input.esc_pressed {
+    if input.just_pressed(Action::Back) {
       input.clear();
       events.push(Event::ButtonPressed);
       state.game_state = GameState::MainMenu;
 
-      input.esc_pressed = false;
+      input.consume(Action::Back);
     }
 
-    if input.p1_up_pressed {
-      let position = (
-        state.player1.position().x,
-        state.player1.position().y + util::PLAYER_SPEED,
-      );
-      state.player1.update_position(position.into());
+    // a stick only steers while the digital direction is neutral, so both together aren't
+    // faster than either
+    let mut direction = input.direction(
+      Action::P1Up,
+      Action::P1Down,
+      state.settings.direction_policy[0],
+    );
+    if direction == 0.0 {
+      direction = input.p1_axis;
     }
-    if input.p1_down_pressed {
+    if direction != 0.0 {
       let position = (
         state.player1.position().x,
-        state.player1.position().y - util::PLAYER_SPEED,
+        state.player1.position().y + util::PLAYER_SPEED * direction,
       );
       state.player1.update_position(position.into());
     }
-    if input.p2_up_pressed {
-      let position = (
-        state.player2.position().x,
-        state.player2.position().y + util::PLAYER_SPEED,
-      );
-      state.player2.update_position(position.into());
+    let mut direction = input.direction(
+      Action::P2Up,
+      Action::P2Down,
+      state.settings.direction_policy[1],
+    );
+    if direction == 0.0 {
+      direction = input.p2_axis;
     }
-    if input.p2_down_pressed {
+    if direction != 0.0 {
       let position = (
         state.player2.position().x,
-        state.player2.position().y - util::PLAYER_SPEED,
+        state.player2.position().y + util::PLAYER_SPEED * direction,
       );
       state.player2.update_position(position.into());
     }
 
+    // a mouse controlled paddle jumps to the cursor height, the clamp below keeps it on court
+    if let Some(pixel) = input.mouse_position {
+      let y = state.viewport.unproject(pixel).y;
+      if state.settings.mouse_control[0] {
+        let position = (state.player1.position().x, y);
+        state.player1.update_position(position.into());
+      }
+      if state.settings.mouse_control[1] {
+        let position = (state.player2.position().x, y);
+        state.player2.update_position(position.into());
+      }
+    }
+
     // normalize players
-    if state.player1.position().y > 1.0 - state.player1.size().y * 0.5 {
+    if state.player1.position().y > COURT_HALF_HEIGHT - state.player1.size().y * 0.5 {
       let position = (
         state.player1.position().x,
-        1.0 - state.player1.size().y * 0.5,
+        COURT_HALF_HEIGHT - state.player1.size().y * 0.5,
       );
       state.player1.update_position(position.into());
-    } else if state.player1.position().y < state.player1.size().y * 0.5 - 1.0 {
+    } else if state.player1.position().y < state.player1.size().y * 0.5 - COURT_HALF_HEIGHT {
       let position = (
         state.player1.position().x,
-        state.player1.size().y * 0.5 - 1.0,
+        state.player1.size().y * 0.5 - COURT_HALF_HEIGHT,
       );
       state.player1.update_position(position.into());
     }
-    if state.player2.position().y > 1.0 - state.player2.size().y * 0.5 {
+    if state.player2.position().y > COURT_HALF_HEIGHT - state.player2.size().y * 0.5 {
       let position = (
         state.player2.position().x,
-        1.0 - state.player2.size().y * 0.5,
+        COURT_HALF_HEIGHT - state.player2.size().y * 0.5,
       );
       state.player2.update_position(position.into());
-    } else if state.player2.position().y < state.player2.size().y * 0.5 - 1.0 {
+    } else if state.player2.position().y < state.player2.size().y * 0.5 - COURT_HALF_HEIGHT {
       let position = (
         state.player2.position().x,
-        state.player2.size().y * 0.5 - 1.0,
+        state.player2.size().y * 0.5 - COURT_HALF_HEIGHT,
       );
       state.player2.update_position(position.into());
     }
@@ -154,18 +297,36 @@ pub struct PauseSystem;
 impl System for PauseSystem {
   fn start(&mut self, state: &mut State) {
     state.title_text.render_text.text = String::from("Paused");
-    state.play_button.render_text.text = String::from("Resume");
-    state.play_button.render_text.focused = true;
+    state.pause_menu.reset_focus();
   }
 
   fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
-    if state.play_button.focused() && input.enter_pressed {
+    if input.just_pressed(Action::Back) {
+      input.clear();
       events.push(Event::ButtonPressed);
-      state.game_state = GameState::Playing;
+      state.game_state = GameState::MainMenu;
+
+      input.consume(Action::Back);
+    }
+
+    match state.pause_menu.update(input, events) {
+      Some(MenuAction::Resume) => state.game_state = GameState::Playing,
+      Some(MenuAction::MainMenu) => state.game_state = GameState::MainMenu,
+      Some(MenuAction::Quit) => state.game_state = GameState::Quitting,
+      _ => (),
     }
   }
 }
 
+#[derive(Debug)]
+pub struct ArenaSystem;
+
+impl System for ArenaSystem {
+  fn update_state(&self, _input: &mut Input, state: &mut State, _events: &mut Vec<Event>) {
+    state.arena.advance();
+  }
+}
+
 #[derive(Debug)]
 pub struct BallSystem;
 
@@ -173,126 +334,212 @@ impl System for BallSystem {
   fn update_state(&self, _input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
     // bounce the ball off the players
     if state.player1.contains(&state.ball) {
-      events.push(Event::BallBounce(state.ball.position()));
       state.ball.position().x -= state.ball.velocity.x - state.player1.size().x;
+      let contact = util::contact_ratio(&state.ball, &state.player1);
       state.ball.velocity = util::calc_ball_velocity(&state.ball, &state.player1);
+      events.push(Event::PaddleHit {
+        player: 0,
+        position: state.ball.position(),
+        speed: state.ball.velocity.magnitude(),
+        contact,
+      });
     } else if state.player2.contains(&state.ball) {
-      events.push(Event::BallBounce(state.ball.position()));
       state.ball.position().x -= state.ball.velocity.x + state.player2.size().x;
       state.ball.velocity.x *= -state.player2.size().y;
+      let contact = util::contact_ratio(&state.ball, &state.player2);
       state.ball.velocity = util::calc_ball_velocity(&state.ball, &state.player2);
+      events.push(Event::PaddleHit {
+        player: 1,
+        position: state.ball.position(),
+        speed: state.ball.velocity.magnitude(),
+        contact,
+      });
     }
 
     state
       .ball
       .update_position(state.ball.position() + state.ball.velocity);
-    if state.ball.position().y > 1.0 {
-      events.push(Event::BallBounce(state.ball.position()));
-      state.ball.position().y = 1.0;
+    for block in state.arena.blocks() {
+      if block.bounce(&mut state.ball) {
+        events.push(Event::BallBounce(state.ball.position()));
+      }
+    }
+    let position = state.ball.position();
+    if position.y.abs() > COURT_HALF_HEIGHT {
+      events.push(Event::BallBounce(position));
+      state
+        .ball
+        .update_position((position.x, COURT_HALF_HEIGHT.copysign(position.y)).into());
       state.ball.velocity.y *= -1.0;
-    } else if state.ball.position().y < -1.0 {
+    }
+
+    // the ball only scores through the goal openings, elsewhere it comes off the back wall
+    if state.ball.position().x > COURT_HALF_WIDTH
+      && !state.arena.right_goal.contains(state.ball.position().y)
+    {
       events.push(Event::BallBounce(state.ball.position()));
-      state.ball.position().y = -1.0;
-      state.ball.velocity.y *= -1.0;
+      state
+        .ball
+        .update_position((COURT_HALF_WIDTH, state.ball.position().y).into());
+      state.ball.velocity.x = -state.ball.velocity.x.abs();
+    } else if state.ball.position().x < -COURT_HALF_WIDTH
+      && !state.arena.left_goal.contains(state.ball.position().y)
+    {
+      events.push(Event::BallBounce(state.ball.position()));
+      state
+        .ball
+        .update_position((-COURT_HALF_WIDTH, state.ball.position().y).into());
+      state.ball.velocity.x = state.ball.velocity.x.abs();
     }
 
-    if state.ball.position().x > 1.0 {
+    if state.ball.position().x > COURT_HALF_WIDTH {
       state.player1.score += 1;
-      if state.player1.score >= 5 {
+      events.push(Event::Score(0));
+      if state.player1.score >= state.rules.points_to_win {
         state.game_state = GameState::GameOver;
       } else {
         state.game_state = GameState::Serving;
-        events.push(Event::Score(0));
       }
-    } else if state.ball.position().x < -1.0 {
+    } else if state.ball.position().x < -COURT_HALF_WIDTH {
       state.player2.score += 1;
-      if state.player2.score >= 5 {
+      events.push(Event::Score(1));
+      if state.player2.score >= state.rules.points_to_win {
         state.game_state = GameState::GameOver;
       } else {
         state.game_state = GameState::Serving;
-        events.push(Event::Score(1));
       }
     }
   }
 }
 
-pub struct ServingSystem {
-  last_time: std::time::Instant,
-}
-
-impl ServingSystem {
-  pub fn new() -> Self {
-    Self {
-      last_time: std::time::Instant::now(),
-    }
-  }
-}
+#[derive(Debug)]
+pub struct ServingSystem;
 
 impl System for ServingSystem {
   fn start(&mut self, state: &mut State) {
-    self.last_time = std::time::Instant::now();
+    state.serve_ticks = util::SERVE_DELAY_TICKS;
     let direction = state.ball.position().x.signum();
+    let angle = state
+      .rng
+      .gen_range(-util::MAX_SERVE_ANGLE, util::MAX_SERVE_ANGLE);
     state.ball.update_position((0.0, 0.0).into());
-    state.ball.velocity = cgmath::Vector2::unit_x() * direction * -util::BALL_SPEED;
-    state.player1_score.render_text.text = format!("{}", state.player1.score);
-    state.player2_score.render_text.text = format!("{}", state.player2.score);
+    state.ball.velocity = cgmath::Vector2 {
+      x: angle.cos() * direction * -util::BALL_SPEED,
+      y: angle.sin() * util::BALL_SPEED,
+    };
   }
 
   fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
-    if input.esc_pressed {
+    if input.just_pressed(Action::Back) {
       input.clear();
       events.push(Event::ButtonPressed);
       state.game_state = GameState::MainMenu;
 
-      input.esc_pressed = false;
+      input.consume(Action::Back);
     }
 
-    let current_time = std::time::Instant::now();
-    let delta_time = current_time - self.last_time;
-    if delta_time.as_secs_f32() > 2.0 {
+    if state.serve_ticks > 0 {
+      state.serve_ticks -= 1;
+    } else {
+      // the server is whoever the ball is moving away from
+      let server = if state.ball.velocity.x > 0.0 { 0 } else { 1 };
+      events.push(Event::Serve(server));
       state.game_state = GameState::Playing;
     }
   }
 }
 
-pub struct GameOverSystem {
-  last_time: std::time::Instant,
-}
-
-impl GameOverSystem {
-  pub fn new() -> Self {
-    Self {
-      last_time: std::time::Instant::now(),
-    }
-  }
-}
+#[derive(Debug)]
+pub struct GameOverSystem;
 
 impl System for GameOverSystem {
   fn start(&mut self, state: &mut State) {
-    self.last_time = std::time::Instant::now();
-
-    state.player1_score.render_text.text = format!("{}", state.player1.score);
-    state.player2_score.render_text.text = format!("{}", state.player2.score);
-
+    state.game_over_ticks = util::GAME_OVER_DELAY_TICKS;
     state.win_text.render_text.text = if state.player1.score > state.player2.score {
       String::from("Player 1 wins!")
     } else {
       String::from("Player 2 wins!")
     };
+
+    state.stats_text.render_text.text = state.stats.summary();
+    state.game_over_menu.reset_focus();
+    if state.replaying {
+      return;
+    }
+    let record = MatchRecord {
+      date: MatchRecord::now(),
+      mode: String::from(state.mode.name()),
+      arena: state.arena.name.clone(),
+      players: [String::from("Player 1"), String::from("Player 2")],
+      score: [state.player1.score, state.player2.score],
+      duration_secs: state.stats.duration().as_secs_f32(),
+      longest_rally: state.stats.longest_rally,
+    };
+    match Records::load() {
+      Ok(mut records) => {
+        records.push(record);
+        if let Err(e) = records.save() {
+          eprintln!("{}", e);
+        }
+      }
+      Err(e) => eprintln!("not saving match, {}", e),
+    }
   }
 
   fn update_state(&self, input: &mut Input, state: &mut State, events: &mut Vec<Event>) {
-    if input.esc_pressed {
+    if input.just_pressed(Action::Back) {
       events.push(Event::ButtonPressed);
       state.game_state = GameState::Quitting;
 
-      input.esc_pressed = false;
+      input.consume(Action::Back);
+    }
+
+    match state.game_over_menu.update(input, events) {
+      Some(MenuAction::PlayAgain) => {
+        state.player1.score = 0;
+        state.player2.score = 0;
+        state.player1.update_y_position(0.0);
+        state.player2.update_y_position(0.0);
+        state.arena.reset();
+        state.stats = MatchStats::new();
+        state.game_state = GameState::Serving;
+      }
+      Some(MenuAction::MainMenu) => state.game_state = GameState::MainMenu,
+      _ => (),
     }
 
-    let current_time = std::time::Instant::now();
-    let delta_time = current_time - self.last_time;
-    if delta_time.as_secs_f32() > 5.0 {
+    if state.game_over_ticks > 0 {
+      state.game_over_ticks -= 1;
+    } else if state.game_state == GameState::GameOver {
       state.game_state = GameState::MainMenu;
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::arena::Arena;
+
+  // how far player 1's paddle moves in one tick of play
+  fn p1_moved(held: &[Action], axis: f32) -> f32 {
+    let mut state = State::new(Arena::builtin(), false);
+    state.game_state = GameState::Playing;
+    let mut input = Input::new();
+    for action in held {
+      input.set(*action, true);
+    }
+    input.p1_axis = axis;
+
+    let before = state.player1.position().y;
+    PlaySystem.update_state(&mut input, &mut state, &mut Vec::new());
+    state.player1.position().y - before
+  }
+
+  #[test]
+  fn stick_steers_only_without_the_keys() {
+    assert_eq!(p1_moved(&[], 0.5), util::PLAYER_SPEED * 0.5);
+    assert_eq!(p1_moved(&[Action::P1Up], 1.0), util::PLAYER_SPEED);
+    assert_eq!(p1_moved(&[Action::P1Down], 1.0), -util::PLAYER_SPEED);
+  }
+}
@@ -0,0 +1,540 @@
+use crate::ai::{Ai, Difficulty};
+use crate::bot::{self, Bot};
+use crate::env::{PaddleAction, PongEnv};
+use crate::util;
+use serde::Serialize;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TournamentError {
+    UnknownArena(String),
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TournamentError::UnknownArena(name) => write!(f, "unknown arena {}", name),
+        }
+    }
+}
+
+impl std::error::Error for TournamentError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entrant {
+    Ai(Difficulty),
+    // a command line for a program speaking the bot protocol
+    Bot(String),
+}
+
+impl Entrant {
+    // "ai:hard" or "bot:python3 my_bot.py"
+    pub fn parse(spec: &str) -> Option<Entrant> {
+        if let Some(name) = spec.strip_prefix("ai:") {
+            Difficulty::from_name(name).map(Entrant::Ai)
+        } else if let Some(command) = spec.strip_prefix("bot:") {
+            if command.trim().is_empty() {
+                None
+            } else {
+                Some(Entrant::Bot(String::from(command)))
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entrant::Ai(difficulty) => write!(f, "ai:{}", difficulty.name()),
+            Entrant::Bot(command) => write!(f, "bot:{}", command),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    RoundRobin,
+    Knockout,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::RoundRobin => "round-robin",
+            Format::Knockout => "knockout",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        [Format::RoundRobin, Format::Knockout]
+            .iter()
+            .copied()
+            .find(|format| format.name() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub format: Format,
+    pub points_to_win: u32,
+    // None plays on the classic court
+    pub arena: Option<String>,
+    pub seed: u64,
+    // a match still going after this many ticks goes to whoever leads, or is drawn
+    pub max_ticks: u64,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            format: Format::RoundRobin,
+            points_to_win: 5,
+            arena: None,
+            seed: 0,
+            max_ticks: 10 * 60 * util::TICKS_PER_SECOND as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchResult {
+    pub round: u32,
+    // entries are positions in the entrant list, so two copies of the same ai can be told apart
+    pub entries: [usize; 2],
+    pub players: [String; 2],
+    pub score: [u32; 2],
+    pub ticks: u64,
+    // None when the match was drawn
+    pub winner: Option<usize>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Standing {
+    pub entry: usize,
+    pub name: String,
+    pub played: u32,
+    pub won: u32,
+    pub drawn: u32,
+    pub lost: u32,
+    pub points_for: u32,
+    pub points_against: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TournamentResult {
+    pub format: String,
+    pub arena: String,
+    pub points_to_win: u32,
+    pub seed: u64,
+    pub matches: Vec<MatchResult>,
+    pub standings: Vec<Standing>,
+}
+
+impl TournamentResult {
+    pub fn matches_csv(&self) -> String {
+        let mut csv =
+            String::from("round,entry1,player1,entry2,player2,score1,score2,ticks,winner,note\n");
+        for result in &self.matches {
+            let winner = match result.winner {
+                Some(side) => csv_field(&result.players[side]),
+                None => String::from("draw"),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                result.round,
+                result.entries[0] + 1,
+                csv_field(&result.players[0]),
+                result.entries[1] + 1,
+                csv_field(&result.players[1]),
+                result.score[0],
+                result.score[1],
+                result.ticks,
+                winner,
+                csv_field(result.note.as_deref().unwrap_or("")),
+            ));
+        }
+        csv
+    }
+
+    pub fn standings_csv(&self) -> String {
+        let mut csv =
+            String::from("rank,entry,name,played,won,drawn,lost,points_for,points_against\n");
+        for (rank, standing) in self.standings.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                rank + 1,
+                standing.entry + 1,
+                csv_field(&standing.name),
+                standing.played,
+                standing.won,
+                standing.drawn,
+                standing.lost,
+                standing.points_for,
+                standing.points_against,
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+enum Controller {
+    Ai(Ai),
+    Bot(Bot),
+}
+
+struct Outcome {
+    score: [u32; 2],
+    ticks: u64,
+    winner: Option<usize>,
+    note: Option<String>,
+}
+
+fn new_env(config: &TournamentConfig) -> Result<PongEnv, TournamentError> {
+    let mut env = PongEnv::new();
+    env.set_points_to_win(config.points_to_win);
+    if let Some(arena) = &config.arena {
+        if !env.select_arena(arena) {
+            return Err(TournamentError::UnknownArena(arena.clone()));
+        }
+    }
+    Ok(env)
+}
+
+// a single match on the config's seed, on_tick sees the match after every tick
+pub fn play(
+    entrants: [&Entrant; 2],
+    config: &TournamentConfig,
+    mut on_tick: impl FnMut(&PongEnv),
+) -> Result<MatchResult, TournamentError> {
+    let mut env = new_env(config)?;
+    let entrants = [entrants[0].clone(), entrants[1].clone()];
+    let outcome = play_match(
+        &mut env,
+        &entrants,
+        [0, 1],
+        config.seed,
+        config,
+        &mut on_tick,
+    );
+    Ok(match_result(1, &entrants, [0, 1], outcome))
+}
+
+pub fn run(
+    entrants: &[Entrant],
+    config: &TournamentConfig,
+) -> Result<TournamentResult, TournamentError> {
+    let mut env = new_env(config)?;
+
+    let mut matches = Vec::new();
+    match config.format {
+        Format::RoundRobin => {
+            for (round, pairs) in round_robin(entrants.len()).into_iter().enumerate() {
+                for pair in pairs {
+                    let seed = config.seed.wrapping_add(matches.len() as u64);
+                    let outcome = play_match(&mut env, entrants, pair, seed, config, &mut |_| ());
+                    matches.push(match_result(round as u32 + 1, entrants, pair, outcome));
+                }
+            }
+        }
+        Format::Knockout => {
+            let mut remaining: Vec<usize> = (0..entrants.len()).collect();
+            let mut round = 1;
+            while remaining.len() > 1 {
+                let mut next = Vec::new();
+                for pair in remaining.chunks(2) {
+                    if pair.len() == 1 {
+                        // odd one out gets a bye
+                        next.push(pair[0]);
+                        continue;
+                    }
+                    let pair = [pair[0], pair[1]];
+                    let seed = config.seed.wrapping_add(matches.len() as u64);
+                    let outcome = play_match(&mut env, entrants, pair, seed, config, &mut |_| ());
+                    // a draw goes to the higher seed, which is the earlier entry
+                    next.push(pair[outcome.winner.unwrap_or(0)]);
+                    matches.push(match_result(round, entrants, pair, outcome));
+                }
+                remaining = next;
+                round += 1;
+            }
+        }
+    }
+
+    Ok(TournamentResult {
+        format: String::from(config.format.name()),
+        arena: String::from(env.arena_name()),
+        points_to_win: config.points_to_win,
+        seed: config.seed,
+        standings: standings(entrants, &matches),
+        matches,
+    })
+}
+
+// the circle method, every entry meets every other once and nobody plays twice in a round
+fn round_robin(count: usize) -> Vec<Vec<[usize; 2]>> {
+    let mut slots: Vec<Option<usize>> = (0..count).map(Some).collect();
+    if count % 2 == 1 {
+        slots.push(None);
+    }
+
+    let mut rounds = Vec::new();
+    for _ in 1..slots.len() {
+        let half = slots.len() / 2;
+        let pairs = (0..half)
+            .filter_map(|i| match (slots[i], slots[slots.len() - 1 - i]) {
+                (Some(a), Some(b)) => Some([a.min(b), a.max(b)]),
+                _ => None,
+            })
+            .collect();
+        rounds.push(pairs);
+        slots[1..].rotate_right(1);
+    }
+    rounds
+}
+
+fn play_match(
+    env: &mut PongEnv,
+    entrants: &[Entrant],
+    pair: [usize; 2],
+    seed: u64,
+    config: &TournamentConfig,
+    on_tick: &mut impl FnMut(&PongEnv),
+) -> Outcome {
+    let mut controllers = Vec::new();
+    for (side, entry) in pair.iter().enumerate() {
+        match &entrants[*entry] {
+            Entrant::Ai(difficulty) => controllers.push(Controller::Ai(Ai::new(side, *difficulty))),
+            Entrant::Bot(command) => match Bot::from_command(command, bot::DEFAULT_TIMEOUT) {
+                Ok(bot) => controllers.push(Controller::Bot(bot)),
+                Err(e) => return forfeit(side, [0, 0], 0, e),
+            },
+        }
+    }
+
+    env.reset(seed);
+    let mut ticks = 0;
+    while !env.done() && ticks < config.max_ticks {
+        let mut actions = [PaddleAction::Stay; 2];
+        for (side, controller) in controllers.iter_mut().enumerate() {
+            actions[side] = match controller {
+                Controller::Ai(ai) => ai.action(env.state()),
+                Controller::Bot(bot) => match bot.act_waiting(env.state(), side as u32) {
+                    Ok(action) => action,
+                    Err(e) => {
+                        let (p1, p2) = env.score();
+                        return forfeit(side, [p1, p2], ticks, e);
+                    }
+                },
+            };
+        }
+        env.step(actions[0], actions[1]);
+        on_tick(env);
+        ticks += 1;
+    }
+
+    let (p1, p2) = env.score();
+    let winner = if p1 > p2 {
+        Some(0)
+    } else if p2 > p1 {
+        Some(1)
+    } else {
+        None
+    };
+    let note = if env.done() {
+        None
+    } else {
+        Some(String::from("tick limit"))
+    };
+    Outcome {
+        score: [p1, p2],
+        ticks,
+        winner,
+        note,
+    }
+}
+
+fn forfeit(side: usize, score: [u32; 2], ticks: u64, error: bot::BotError) -> Outcome {
+    Outcome {
+        score,
+        ticks,
+        winner: Some(1 - side),
+        note: Some(format!("player {} forfeits: {}", side + 1, error)),
+    }
+}
+
+fn match_result(
+    round: u32,
+    entrants: &[Entrant],
+    pair: [usize; 2],
+    outcome: Outcome,
+) -> MatchResult {
+    MatchResult {
+        round,
+        entries: pair,
+        players: [entrants[pair[0]].to_string(), entrants[pair[1]].to_string()],
+        score: outcome.score,
+        ticks: outcome.ticks,
+        winner: outcome.winner,
+        note: outcome.note,
+    }
+}
+
+// most wins first, then draws, then points difference
+fn standings(entrants: &[Entrant], matches: &[MatchResult]) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = entrants
+        .iter()
+        .enumerate()
+        .map(|(entry, entrant)| Standing {
+            entry,
+            name: entrant.to_string(),
+            ..Default::default()
+        })
+        .collect();
+
+    for result in matches {
+        for side in 0..2 {
+            let standing = &mut standings[result.entries[side]];
+            standing.played += 1;
+            standing.points_for += result.score[side];
+            standing.points_against += result.score[1 - side];
+            match result.winner {
+                Some(winner) if winner == side => standing.won += 1,
+                Some(_) => standing.lost += 1,
+                None => standing.drawn += 1,
+            }
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        let difference = |s: &Standing| s.points_for as i64 - s.points_against as i64;
+        b.won
+            .cmp(&a.won)
+            .then(b.drawn.cmp(&a.drawn))
+            .then(difference(b).cmp(&difference(a)))
+            .then(a.entry.cmp(&b.entry))
+    });
+    standings
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(entries: [usize; 2], score: [u32; 2], winner: Option<usize>) -> MatchResult {
+        MatchResult {
+            round: 1,
+            entries,
+            players: [
+                format!("entry {}", entries[0]),
+                format!("entry {}", entries[1]),
+            ],
+            score,
+            ticks: 0,
+            winner,
+            note: None,
+        }
+    }
+
+    fn ais(count: usize) -> Vec<Entrant> {
+        vec![Entrant::Ai(Difficulty::Normal); count]
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        for count in 2..=7 {
+            let rounds = round_robin(count);
+            let mut pairs = Vec::new();
+            for round in &rounds {
+                let mut playing: Vec<usize> = round.iter().flatten().copied().collect();
+                playing.sort_unstable();
+                playing.dedup();
+                assert_eq!(playing.len(), round.len() * 2, "someone plays twice");
+                // with an odd count exactly one entry sits each round out
+                assert_eq!(count - playing.len(), count % 2);
+                pairs.extend(round.iter().copied());
+            }
+
+            pairs.sort_unstable();
+            let mut expected = Vec::new();
+            for a in 0..count {
+                for b in a + 1..count {
+                    expected.push([a, b]);
+                }
+            }
+            assert_eq!(pairs, expected, "{} entries", count);
+        }
+    }
+
+    #[test]
+    fn knockout_gives_the_odd_one_out_a_bye() {
+        // a single tick draws every match, which goes to the earlier entry
+        let config = TournamentConfig {
+            format: Format::Knockout,
+            max_ticks: 1,
+            ..Default::default()
+        };
+        let result = run(&ais(5), &config).unwrap();
+        let bracket: Vec<(u32, [usize; 2])> = result
+            .matches
+            .iter()
+            .map(|result| (result.round, result.entries))
+            .collect();
+        assert_eq!(
+            bracket,
+            vec![(1, [0, 1]), (1, [2, 3]), (2, [0, 2]), (3, [0, 4])]
+        );
+    }
+
+    #[test]
+    fn standings_break_ties_on_points_difference() {
+        let matches = [
+            result([0, 1], [5, 3], Some(0)),
+            result([2, 3], [5, 0], Some(0)),
+            result([0, 2], [2, 2], None),
+            result([1, 3], [4, 4], None),
+            result([1, 2], [5, 4], Some(0)),
+        ];
+        let order: Vec<usize> = standings(&ais(4), &matches)
+            .iter()
+            .map(|standing| standing.entry)
+            .collect();
+        // 0, 1 and 2 each won one and drew one so the points difference decides, 3 never won
+        assert_eq!(order, vec![2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn unknown_arenas_are_an_error() {
+        let config = TournamentConfig {
+            arena: Some(String::from("Nowhere")),
+            ..Default::default()
+        };
+        match run(&ais(2), &config) {
+            Err(TournamentError::UnknownArena(name)) => assert_eq!(name, "Nowhere"),
+            other => panic!("expected an unknown arena, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("ai:hard"), "ai:hard");
+        assert_eq!(csv_field("bot:run a,b"), "\"bot:run a,b\"");
+        assert_eq!(csv_field("bot:say \"hi\""), "\"bot:say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,334 @@
+use crate::input::Action;
+use crate::render::{Quad, RenderSink, RenderText};
+use crate::session::Session;
+use crate::state::GameState;
+use crate::util;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// terminal cells are about twice as tall as they are wide, the game lays itself out as if the
+// window were made of cells this many pixels across
+const CELL_WIDTH: f32 = 15.0;
+const CELL_HEIGHT: f32 = 30.0;
+// terminals only report key presses, so a paddle key counts as held until its repeats stop
+// coming. the first repeat takes the longest to arrive
+const FIRST_REPEAT: Duration = Duration::from_millis(500);
+const NEXT_REPEAT: Duration = Duration::from_millis(100);
+const BLOCK: char = '█';
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Style {
+    Normal,
+    Dim,
+    Focused,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Cell {
+    symbol: char,
+    style: Style,
+}
+
+const EMPTY: Cell = Cell {
+    symbol: ' ',
+    style: Style::Normal,
+};
+
+// the frame being drawn and the one on screen, only cells that changed get written out
+struct Canvas {
+    columns: u16,
+    rows: u16,
+    cells: Vec<Cell>,
+    shown: Vec<Cell>,
+}
+
+impl Canvas {
+    fn new((columns, rows): (u16, u16)) -> Self {
+        let size = columns as usize * rows as usize;
+        Self {
+            columns,
+            rows,
+            cells: vec![EMPTY; size],
+            // nothing matches, so the first flush writes every cell
+            shown: vec![
+                Cell {
+                    symbol: '\0',
+                    style: Style::Normal,
+                };
+                size
+            ],
+        }
+    }
+
+    fn window_size(&self) -> (f32, f32) {
+        (
+            self.columns as f32 * CELL_WIDTH,
+            self.rows as f32 * CELL_HEIGHT,
+        )
+    }
+
+    fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = EMPTY;
+        }
+    }
+
+    fn put(&mut self, column: i32, row: i32, cell: Cell) {
+        if column >= 0 && row >= 0 && column < self.columns as i32 && row < self.rows as i32 {
+            self.cells[row as usize * self.columns as usize + column as usize] = cell;
+        }
+    }
+
+    fn flush(&mut self, out: &mut impl Write) -> crossterm::Result<()> {
+        let mut style = None;
+        let mut cursor_at = None;
+        for (i, (cell, shown)) in self.cells.iter().zip(self.shown.iter_mut()).enumerate() {
+            if cell == shown {
+                continue;
+            }
+            *shown = *cell;
+
+            if cursor_at != Some(i) {
+                let column = (i % self.columns as usize) as u16;
+                let row = (i / self.columns as usize) as u16;
+                queue!(out, cursor::MoveTo(column, row))?;
+            }
+            if style != Some(cell.style) {
+                queue!(out, SetAttribute(Attribute::Reset))?;
+                match cell.style {
+                    Style::Normal => (),
+                    Style::Dim => queue!(out, SetForegroundColor(Color::DarkGrey))?,
+                    Style::Focused => queue!(out, SetAttribute(Attribute::Reverse))?,
+                }
+                style = Some(cell.style);
+            }
+            queue!(out, Print(cell.symbol))?;
+            cursor_at = Some(i + 1);
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl RenderSink for Canvas {
+    // fills the cells whose centres fall inside the quad, always at least the one under its
+    // centre so the ball never disappears between cells
+    fn push_quad(&mut self, quad: &Quad) {
+        let (columns, rows) = (self.columns as f32, self.rows as f32);
+        let to_column = |x: f32| (x + 1.0) * 0.5 * columns;
+        let to_row = |y: f32| (1.0 - y) * 0.5 * rows;
+        let radii = quad.size * 0.5;
+
+        let left = to_column(quad.position.x - radii.x);
+        let right = to_column(quad.position.x + radii.x);
+        let top = to_row(quad.position.y + radii.y);
+        let bottom = to_row(quad.position.y - radii.y);
+
+        let block = Cell {
+            symbol: BLOCK,
+            style: Style::Normal,
+        };
+        self.put(
+            to_column(quad.position.x) as i32,
+            to_row(quad.position.y) as i32,
+            block,
+        );
+        for row in (top - 0.5).ceil() as i32..(bottom - 0.5).ceil() as i32 {
+            for column in (left - 0.5).ceil() as i32..(right - 0.5).ceil() as i32 {
+                self.put(column, row, block);
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &RenderText) {
+        let brightness = (text.color.x + text.color.y + text.color.z) / 3.0;
+        let style = if text.focused {
+            Style::Focused
+        } else if brightness < 0.7 {
+            Style::Dim
+        } else {
+            Style::Normal
+        };
+
+        let lines: Vec<&str> = text.text.lines().collect();
+        let mut row = (text.position.y / CELL_HEIGHT) as i32;
+        if text.centered {
+            row -= lines.len() as i32 / 2;
+        }
+        for line in lines {
+            let mut column = (text.position.x / CELL_WIDTH) as i32;
+            if text.centered {
+                column -= line.chars().count() as i32 / 2;
+            }
+            for symbol in line.chars() {
+                self.put(column, row, Cell { symbol, style });
+                column += 1;
+            }
+            row += 1;
+        }
+    }
+}
+
+// paddle keys are held until their repeats stop, every other key is let go after one tick so
+// menus step once per press or repeat
+#[derive(Default)]
+struct HeldKeys {
+    held: Vec<(Action, Instant)>,
+}
+
+impl HeldKeys {
+    fn press(&mut self, session: &mut Session, action: Action) {
+        let paddle = matches!(
+            action,
+            Action::P1Up | Action::P1Down | Action::P2Up | Action::P2Down
+        );
+        let playing = crate::any!(
+            session.state().game_state,
+            GameState::Serving,
+            GameState::Playing
+        );
+
+        let now = Instant::now();
+        match self.held.iter_mut().find(|(held, _)| *held == action) {
+            Some((_, until)) if paddle && playing => *until = now + NEXT_REPEAT,
+            Some(_) => (),
+            None => {
+                session.input.set(action, true);
+                let until = if paddle && playing {
+                    now + FIRST_REPEAT
+                } else {
+                    now
+                };
+                self.held.push((action, until));
+            }
+        }
+    }
+
+    fn release_expired(&mut self, session: &mut Session) {
+        let now = Instant::now();
+        self.held.retain(|(action, until)| {
+            if *until > now {
+                return true;
+            }
+            session.input.set(*action, false);
+            false
+        });
+    }
+}
+
+// raw mode and the alternate screen are undone however the game ends
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> crossterm::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            event::EnableMouseCapture
+        )?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            event::DisableMouseCapture,
+            SetAttribute(Attribute::Reset),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn action(key: KeyEvent) -> Option<Action> {
+    let action = match key.code {
+        KeyCode::Up => Action::P2Up,
+        KeyCode::Down => Action::P2Down,
+        KeyCode::Left => Action::Left,
+        KeyCode::Right => Action::Right,
+        KeyCode::Enter => Action::Confirm,
+        KeyCode::Esc => Action::Back,
+        KeyCode::F(10) => Action::Record,
+        KeyCode::F(12) => Action::Screenshot,
+        KeyCode::Char(c) => match c.to_ascii_lowercase() {
+            'w' => Action::P1Up,
+            's' => Action::P1Down,
+            'a' => Action::Left,
+            'd' => Action::Right,
+            ' ' => Action::Confirm,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(action)
+}
+
+// plays the game in the terminal until it quits or ctrl+c is pressed
+pub fn run(mut session: Session) -> crossterm::Result<()> {
+    let _raw_terminal = RawTerminal::enter()?;
+    let mut out = io::stdout();
+    let mut canvas = Canvas::new(terminal::size()?);
+    let mut held_keys = HeldKeys::default();
+    session.start(canvas.window_size());
+
+    let tick = Duration::from_secs(1) / util::TICKS_PER_SECOND;
+    let mut next_tick = Instant::now();
+    while !session.is_quitting() {
+        // handle whatever arrives before the tick is due
+        while event::poll(next_tick.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(key) => {
+                    if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+                        return Ok(());
+                    }
+                    if let Some(action) = action(key) {
+                        held_keys.press(&mut session, action);
+                    }
+                }
+                Event::Mouse(mouse) => {
+                    session.input.mouse_position = Some((
+                        (mouse.column as f32 + 0.5) * CELL_WIDTH,
+                        (mouse.row as f32 + 0.5) * CELL_HEIGHT,
+                    ));
+                    match mouse.kind {
+                        MouseEventKind::Moved | MouseEventKind::Drag(_) => {
+                            session.input.mouse_moved = true;
+                        }
+                        MouseEventKind::Down(MouseButton::Left) => {
+                            session.input.set(Action::Click, true);
+                        }
+                        MouseEventKind::Up(MouseButton::Left) => {
+                            session.input.set(Action::Click, false);
+                        }
+                        _ => (),
+                    }
+                }
+                Event::Resize(columns, rows) => {
+                    canvas = Canvas::new((columns, rows));
+                    session.resize(canvas.window_size());
+                    execute!(out, terminal::Clear(terminal::ClearType::All))?;
+                }
+            }
+        }
+
+        session.update();
+        held_keys.release_expired(&mut session);
+
+        canvas.clear();
+        session.draw(&mut canvas);
+        canvas.flush(&mut out)?;
+
+        // after a stall the game carries on from now rather than rushing to catch up
+        next_tick = (next_tick + tick).max(Instant::now());
+    }
+    Ok(())
+}
@@ -1,22 +1,132 @@
 #![macro_use]
 
+use crate::arena::{Arena, Block};
 use crate::ball::Ball;
 use crate::player::Player;
+use crate::world::COURT_HALF_HEIGHT;
+
+use std::path::PathBuf;
 
 pub const PLAYER_SPEED: f32 = 0.05;
 pub const BALL_SPEED: f32 = 0.025;
+// the game ticks at 60 per second, timers count ticks so headless runs behave the same
+pub const TICKS_PER_SECOND: u32 = 60;
+// the ball is served on the tick after two seconds have passed
+pub const SERVE_DELAY_TICKS: u32 = 2 * TICKS_PER_SECOND;
+pub const GAME_OVER_DELAY_TICKS: u32 = 5 * TICKS_PER_SECOND;
+// serves leave at an angle drawn from the match's rng, up to this far from straight across
+pub const MAX_SERVE_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
 
 const BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+// the furthest ahead a prediction looks, a ball barely moving across the court never arrives
+const MAX_PREDICTION_TICKS: u32 = 10 * TICKS_PER_SECOND;
 
-pub fn calc_ball_velocity(ball: &Ball, player: &Player) -> cgmath::Vector2<f32> {
+// where on the paddle the ball struck, 0 in the middle and 0.25 at the top edge whatever the
+// paddle's size. a little more is possible as the ball overlaps the ends
+pub fn contact_ratio(ball: &Ball, player: &Player) -> f32 {
     let diff_y = ball.position().y - player.position().y;
-    let ratio = diff_y / player.size().y * 0.5;
+    diff_y / player.size().y * 0.5
+}
+
+pub fn calc_ball_velocity(ball: &Ball, player: &Player) -> cgmath::Vector2<f32> {
+    let ratio = contact_ratio(ball, player);
     cgmath::Vector2 {
         x: (BOUNCE_ANGLE * ratio).cos() * -player.position().x.signum(),
         y: (BOUNCE_ANGLE * ratio).sin(),
     } * BALL_SPEED
 }
 
+#[derive(Debug, Clone)]
+pub struct Crossing {
+    // where the ball meets the line, x is the line's
+    pub position: cgmath::Vector2<f32>,
+    // ticks of play until the ball is on or past the line
+    pub ticks: u32,
+    // where the ball bounced on the way, off the walls at y = ±1 and anything in the arena
+    pub bounces: Vec<cgmath::Vector2<f32>>,
+}
+
+// where and when the ball will cross the vertical line at x, moving the way the ball system
+// moves it. paddles are left out, so this is where the ball goes if nobody touches it. None when
+// the ball is heading away from the line or won't reach it in time
+pub fn predict_crossing(ball: &Ball, arena: &Arena, x: f32) -> Option<Crossing> {
+    if (x - ball.position().x) * ball.velocity.x <= 0.0 {
+        return None;
+    }
+
+    // walls never move, so only the moving obstacles are copied to step them ahead. nothing is
+    // allocated in an arena without any
+    let mut moving: Vec<Block> = arena
+        .obstacles
+        .iter()
+        .filter(|obstacle| obstacle.moves())
+        .copied()
+        .collect();
+    let mut ball = {
+        let mut copy = Ball::new(ball.position(), ball.radius());
+        copy.velocity = ball.velocity;
+        copy
+    };
+    let mut bounces = Vec::new();
+    for ticks in 1..=MAX_PREDICTION_TICKS {
+        for obstacle in &mut moving {
+            obstacle.advance();
+        }
+        let previous = ball.position();
+        ball.update_position(previous + ball.velocity);
+
+        // blocks bounce the ball in the same order as Arena::blocks
+        let mut bounced = false;
+        let mut moved = moving.iter();
+        for block in arena
+            .walls
+            .iter()
+            .chain(arena.obstacles.iter().map(|obstacle| {
+                if obstacle.moves() {
+                    moved.next().unwrap_or(obstacle)
+                } else {
+                    obstacle
+                }
+            }))
+        {
+            bounced |= block.bounce(&mut ball);
+        }
+        let mut position = ball.position();
+        if position.y.abs() > COURT_HALF_HEIGHT {
+            position.y = COURT_HALF_HEIGHT.copysign(position.y);
+            ball.update_position(position);
+            ball.velocity.y *= -1.0;
+            bounced = true;
+        }
+        if bounced {
+            bounces.push(position);
+        }
+
+        if (previous.x - x) * (position.x - x) <= 0.0 {
+            let t = if position.x != previous.x {
+                (x - previous.x) / (position.x - previous.x)
+            } else {
+                1.0
+            };
+            return Some(Crossing {
+                position: (x, previous.y + (position.y - previous.y) * t).into(),
+                ticks,
+                bounces,
+            });
+        }
+    }
+    None
+}
+
+// saved data lives in ~/.local/share/pong, or the working directory without a home
+pub fn data_path(file_name: &str) -> PathBuf {
+    let dir = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(".local/share/pong"),
+        None => PathBuf::new(),
+    };
+    dir.join(file_name)
+}
+
 #[macro_export]
 macro_rules! any {
     ($x:expr, $($y:expr),+ $(,)?) => {
@@ -25,3 +135,86 @@ macro_rules! any {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ball(position: (f32, f32), velocity: (f32, f32)) -> Ball {
+        let mut ball = Ball::new(position.into(), 0.025);
+        ball.velocity = velocity.into();
+        ball
+    }
+
+    fn assert_near(actual: cgmath::Vector2<f32>, expected: (f32, f32)) {
+        assert!(
+            (actual.x - expected.0).abs() < 1e-4 && (actual.y - expected.1).abs() < 1e-4,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn predicts_straight_across() {
+        let crossing = predict_crossing(
+            &ball((0.0, 0.0), (BALL_SPEED, 0.0)),
+            &Arena::classic(),
+            0.51,
+        )
+        .unwrap();
+        assert_near(crossing.position, (0.51, 0.0));
+        assert_eq!(crossing.ticks, 21);
+        assert!(crossing.bounces.is_empty());
+    }
+
+    #[test]
+    fn predicts_one_bounce() {
+        let crossing = predict_crossing(
+            &ball((0.0, 0.9), (BALL_SPEED, BALL_SPEED)),
+            &Arena::classic(),
+            0.51,
+        )
+        .unwrap();
+        assert_near(crossing.position, (0.51, 0.615));
+        assert_eq!(crossing.ticks, 21);
+        assert_eq!(crossing.bounces.len(), 1);
+        assert_near(crossing.bounces[0], (0.125, COURT_HALF_HEIGHT));
+    }
+
+    #[test]
+    fn predicts_two_bounces() {
+        let crossing = predict_crossing(
+            &ball((0.0, 0.95), (BALL_SPEED, 0.09)),
+            &Arena::classic(),
+            0.61,
+        )
+        .unwrap();
+        assert_near(crossing.position, (0.61, -0.964));
+        assert_eq!(crossing.ticks, 25);
+        assert_eq!(crossing.bounces.len(), 2);
+        assert_near(crossing.bounces[0], (0.025, COURT_HALF_HEIGHT));
+        assert_near(crossing.bounces[1], (0.6, -COURT_HALF_HEIGHT));
+    }
+
+    #[test]
+    fn counts_a_bounce_on_the_tick_it_crosses() {
+        let crossing = predict_crossing(
+            &ball((0.0, 0.9), (BALL_SPEED, BALL_SPEED)),
+            &Arena::classic(),
+            0.12,
+        )
+        .unwrap();
+        assert_near(crossing.position, (0.12, COURT_HALF_HEIGHT));
+        assert_eq!(crossing.ticks, 5);
+        assert_eq!(crossing.bounces.len(), 1);
+        assert_near(crossing.bounces[0], (0.125, COURT_HALF_HEIGHT));
+    }
+
+    #[test]
+    fn ignores_a_ball_moving_away() {
+        let arena = Arena::classic();
+        assert!(predict_crossing(&ball((0.0, 0.0), (-BALL_SPEED, 0.0)), &arena, 0.5).is_none());
+        assert!(predict_crossing(&ball((0.6, 0.0), (BALL_SPEED, 0.0)), &arena, 0.5).is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::render::Quad;
+
+// the court is COURT_ASPECT times wider than it is tall, gameplay happens in these world units
+// and only gets mapped onto the window when drawing
+pub const COURT_ASPECT: f32 = 4.0 / 3.0;
+pub const COURT_HALF_HEIGHT: f32 = 1.0;
+pub const COURT_HALF_WIDTH: f32 = COURT_HALF_HEIGHT * COURT_ASPECT;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Viewport {
+    scale: cgmath::Vector2<f32>,
+    window_size: (f32, f32),
+}
+
+impl Viewport {
+    // fits the court inside the window, leaving bars on the sides or top and bottom
+    pub fn new(window_size: (f32, f32)) -> Self {
+        let (width, height) = window_size;
+        let window_aspect = if width > 0.0 && height > 0.0 {
+            width / height
+        } else {
+            COURT_ASPECT
+        };
+
+        let scale = if window_aspect > COURT_ASPECT {
+            cgmath::Vector2 {
+                x: COURT_ASPECT / window_aspect / COURT_HALF_WIDTH,
+                y: 1.0 / COURT_HALF_HEIGHT,
+            }
+        } else {
+            cgmath::Vector2 {
+                x: 1.0 / COURT_HALF_WIDTH,
+                y: window_aspect / COURT_ASPECT / COURT_HALF_HEIGHT,
+            }
+        };
+
+        Self { scale, window_size }
+    }
+
+    pub fn project(&self, point: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        cgmath::Vector2 {
+            x: point.x * self.scale.x,
+            y: point.y * self.scale.y,
+        }
+    }
+
+    // maps a window position in pixels, origin top left, back into world units
+    pub fn unproject(&self, pixel: (f32, f32)) -> cgmath::Vector2<f32> {
+        let (width, height) = self.window_size;
+        if width <= 0.0 || height <= 0.0 {
+            return cgmath::Vector2 { x: 0.0, y: 0.0 };
+        }
+        cgmath::Vector2 {
+            x: (pixel.0 / width * 2.0 - 1.0) / self.scale.x,
+            y: (1.0 - pixel.1 / height * 2.0) / self.scale.y,
+        }
+    }
+
+    pub fn quad(&self, quad: &Quad) -> Quad {
+        Quad::new(self.project(quad.position), self.project(quad.size))
+    }
+}
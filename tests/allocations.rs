@@ -0,0 +1,104 @@
+// counts the heap allocations made on the test's own thread, to check that matches step and draw
+// without allocating once they're warmed up
+use pong::arena::Arena;
+use pong::env::{PaddleAction, PongEnv};
+use pong::input::Action;
+use pong::render::Recording;
+use pong::session::Session;
+use pong::state::GameState;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    // the counter is gone while the thread shuts down
+    let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn stepping_a_match_does_not_allocate() {
+    let mut env = PongEnv::new();
+    env.set_points_to_win(21);
+    env.reset(1);
+    // player 2 never moves off the top, so points are scored and served again
+    let mut step = || env.step(PaddleAction::Stay, PaddleAction::Up);
+    for _ in 0..300 {
+        step();
+    }
+    assert_eq!(
+        allocations(|| {
+            for _ in 0..3000 {
+                step();
+            }
+        }),
+        0
+    );
+}
+
+#[test]
+fn updating_and_drawing_a_frame_does_not_allocate() {
+    let mut session = Session::new(Arena::builtin(), false);
+    // a serve shallow enough for the paddles to keep returning without moving
+    session.set_seed(7);
+    session.start((800.0, 600.0));
+    let mut recording = Recording::new();
+    let mut frame = |session: &mut Session| {
+        session.update();
+        recording.clear();
+        session.draw(&mut recording);
+    };
+
+    for _ in 0..2 {
+        frame(&mut session);
+    }
+    assert_eq!(allocations(|| frame(&mut session)), 0);
+
+    // into a match from the main menu, nobody moves so the ball goes back and forth
+    session.input.set(Action::Confirm, true);
+    frame(&mut session);
+    session.input.set(Action::Confirm, false);
+    for _ in 0..2 {
+        frame(&mut session);
+    }
+    assert_eq!(session.state().game_state, GameState::Serving);
+    assert_eq!(
+        allocations(|| {
+            for _ in 0..300 {
+                frame(&mut session);
+            }
+        }),
+        0
+    );
+    assert_eq!(session.state().game_state, GameState::Playing);
+}